@@ -0,0 +1,34 @@
+//! Compares `Adapter::call` with a `BoundAdapter` on libc's `abs`, run with `cargo bench --bench adapter`.
+
+use sffi::adapter::Adapter;
+use sffi::library::LibHandle;
+use std::ffi::c_void;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const CALLS: u32 = 1_000_000;
+
+fn measure(name: &str, mut call: impl FnMut()) -> Duration {
+    for _ in 0..CALLS / 10 { call() }
+    let start = Instant::now();
+    for _ in 0..CALLS { call() }
+    let elapsed = start.elapsed();
+    println!("{:<8} {:>8.1} ns/call", name, elapsed.as_nanos() as f64 / CALLS as f64);
+    elapsed
+}
+
+fn main() {
+    let mut library = LibHandle::open("").unwrap();
+    let func = library.func("abs", "(int)int").unwrap();
+    let adapter = Adapter::from_str("(i64)i64").unwrap();
+    let bound = adapter.bind(func).unwrap();
+
+    let mut argument = -42i64;
+    let mut result = 0i64;
+    let result = &mut result as *mut i64 as *mut c_void;
+    let arguments = &mut [&mut argument as *mut i64 as *mut c_void];
+
+    let adapter = measure("adapter", || adapter.call(black_box(func), black_box(result), black_box(arguments)).unwrap());
+    let bound = measure("bound", || bound.call(black_box(result), black_box(arguments)).unwrap());
+    println!("speedup  {:>8.2}x", adapter.as_secs_f64() / bound.as_secs_f64());
+}
@@ -0,0 +1,333 @@
+#ifndef SFFI_H
+#define SFFI_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct sffi_lib sffi_lib;
+typedef struct sffi_func sffi_func;
+typedef struct sffi_adapter sffi_adapter;
+typedef struct sffi_bound_adapter sffi_bound_adapter;
+typedef struct sffi_struct_type sffi_struct_type;
+typedef struct sffi_error sffi_error;
+
+typedef void *(*sffi_alloc_fn)(size_t size, size_t align, void *data);
+typedef void (*sffi_free_fn)(void *ptr, void *data);
+
+/* Error codes */
+
+#define SFFI_NO_ERR                   0u
+#define SFFI_RUST_STR_TO_C_STR_ERR    1u
+#define SFFI_C_STR_TO_RUST_STR_ERR    2u
+#define SFFI_LIB_OPEN_ERR             3u
+#define SFFI_LIB_SYMBOL_ERR           4u
+#define SFFI_LIB_CLOSE_ERR            5u
+#define SFFI_FFI_BAD_TYPEDEF_ERR      6u
+#define SFFI_FFI_BAD_ABI_ERR          7u
+#define SFFI_FFI_BAD_ARG_TYPEE_ERR    8u
+#define SFFI_INVALID_DESCRIPTOR_ERR   9u
+#define SFFI_INVALID_CAST_ERR         10u
+#define SFFI_INVALID_ARGUMENTS_ERR    11u
+#define SFFI_LIB_NOT_FOUND_ERR        12u
+#define SFFI_LIB_WRONG_ARCH_ERR       13u
+#define SFFI_LIB_MISSING_DEP_ERR      14u
+#define SFFI_LIB_UNDEFINED_SYMBOL_ERR 15u
+#define SFFI_LIB_PERMISSION_ERR       16u
+#define SFFI_PANIC_ERR                17u
+#define SFFI_INVALID_HANDLE_ERR       18u
+#define SFFI_ALLOC_ERR                19u
+
+/* Descriptor syntaxes of sffi_lib_func_ex */
+
+#define SFFI_SYNTAX_SFFI    0u
+#define SFFI_SYNTAX_JNI     1u
+#define SFFI_SYNTAX_DYNCALL 2u
+
+/* Type kinds of the function and structure introspection */
+
+#define SFFI_TYPE_AUTO                  0u
+#define SFFI_TYPE_VOID                  1u
+#define SFFI_TYPE_INT                   2u
+#define SFFI_TYPE_FLOAT                 3u
+#define SFFI_TYPE_DOUBLE                4u
+#define SFFI_TYPE_LONG_DOUBLE           5u
+#define SFFI_TYPE_ISIZE                 6u
+#define SFFI_TYPE_USIZE                 7u
+#define SFFI_TYPE_I8                    8u
+#define SFFI_TYPE_I16                   9u
+#define SFFI_TYPE_I32                   10u
+#define SFFI_TYPE_I64                   11u
+#define SFFI_TYPE_I128                  12u
+#define SFFI_TYPE_U8                    13u
+#define SFFI_TYPE_U16                   14u
+#define SFFI_TYPE_U32                   15u
+#define SFFI_TYPE_U64                   16u
+#define SFFI_TYPE_U128                  17u
+#define SFFI_TYPE_F32                   18u
+#define SFFI_TYPE_F64                   19u
+#define SFFI_TYPE_F128                  20u
+#define SFFI_TYPE_COMPLEX_F32           21u
+#define SFFI_TYPE_COMPLEX_F64           22u
+#define SFFI_TYPE_COMPLEX_LONG_DOUBLE   23u
+#define SFFI_TYPE_POINTER               24u
+#define SFFI_TYPE_REF_STR               25u
+#define SFFI_TYPE_BORROW_STR            26u
+#define SFFI_TYPE_REF_ARRAY             27u
+#define SFFI_TYPE_BORROW_ARRAY          28u
+#define SFFI_TYPE_STRUCT                29u
+#define SFFI_TYPE_ENUM                  30u
+#define SFFI_TYPE_FLAGS                 31u
+
+/* Resources of sffi_debug_live_count */
+
+#define SFFI_DEBUG_LIBRARY       0u
+#define SFFI_DEBUG_ADAPTER       1u
+#define SFFI_DEBUG_STRUCT_TYPE   2u
+#define SFFI_DEBUG_ERROR         3u
+#define SFFI_DEBUG_STRUCT        4u
+#define SFFI_DEBUG_STRING        5u
+#define SFFI_DEBUG_BOUND_ADAPTER 6u
+
+/* Errors, a null error means success.
+   The message is the detail of the innermost error and may be null, the report describes the whole chain */
+
+unsigned int sffi_error_code(const sffi_error *error);
+const char *sffi_error_msg(const sffi_error *error);
+const char *sffi_error_report(const sffi_error *error);
+void sffi_error_free(sffi_error *error);
+
+/* Panics of entry points without an error result, kept per thread like the last error */
+
+bool sffi_poisoned(void);
+const char *sffi_poison_msg(void);
+void sffi_poison_clear(void);
+
+/* Libraries, their functions are freed and their handles invalidated when they are closed.
+   sffi_lib_open binds symbols lazily, sffi_lib_open_now binds them all and reports undefined ones.
+   A NULL name is rejected with SFFI_INVALID_ARGUMENTS_ERR, an empty name opens the main program on glibc */
+
+sffi_error *sffi_lib_open(sffi_lib **r_handle, const char *name);
+sffi_error *sffi_lib_open_now(sffi_lib **r_handle, const char *name);
+sffi_error *sffi_lib_symbol(const void **r_symbol, const sffi_lib *handle, const char *name);
+sffi_error *sffi_lib_func(const sffi_func **r_func, sffi_lib *handle, const char *name, const char *desc);
+sffi_error *sffi_lib_func_ex(const sffi_func **r_func, sffi_lib *handle, const char *name, const char *desc, unsigned int syntax);
+void *sffi_lib_as_raw(const sffi_lib *handle);
+sffi_error *sffi_lib_as_raw_checked(void **r_raw, const sffi_lib *handle);
+void sffi_lib_close(sffi_lib *handle);
+sffi_error *sffi_lib_close_checked(sffi_lib *handle);
+
+/* Functions, `avalue` of the raw calls is an array of libffi's `ffi_raw` */
+
+void sffi_func_call(const sffi_func *func, void *rvalue, void **avalue);
+sffi_error *sffi_func_call_checked(const sffi_func *func, void *rvalue, void **avalue);
+void sffi_func_call_raw(const sffi_func *func, void *rvalue, void *avalue);
+sffi_error *sffi_func_call_raw_checked(const sffi_func *func, void *rvalue, void *avalue);
+const void *sffi_func_as_raw(const sffi_func *func);
+sffi_error *sffi_func_as_raw_checked(const void **r_raw, const sffi_func *func);
+
+/* Function introspection, the struct types and descriptors returned are owned by the caller */
+
+sffi_error *sffi_func_arg_count(unsigned int *r_count, const sffi_func *func);
+sffi_error *sffi_func_arg_type(unsigned int *r_kind, unsigned int *r_size, unsigned int *r_align, const sffi_func *func, unsigned int index);
+sffi_error *sffi_func_ret_type(unsigned int *r_kind, unsigned int *r_size, unsigned int *r_align, const sffi_func *func);
+sffi_error *sffi_func_arg_struct(sffi_struct_type **r_struct_type, const sffi_func *func, unsigned int index);
+sffi_error *sffi_func_ret_struct(sffi_struct_type **r_struct_type, const sffi_func *func);
+sffi_error *sffi_func_desc(char **r_desc, const sffi_func *func);
+
+/* Adapters */
+
+sffi_error *sffi_adapter_parse(sffi_adapter **r_adapter, const char *desc);
+void sffi_adapter_set_strict(sffi_adapter *adapter, bool strict);
+sffi_error *sffi_adapter_set_strict_checked(sffi_adapter *adapter, bool strict);
+/* Strings converted for a leaked argument are kept by the callee and freed with sffi_string_free */
+sffi_error *sffi_adapter_set_leaked(sffi_adapter *adapter, unsigned int index, bool leaked);
+sffi_error *sffi_adapter_call(const sffi_adapter *adapter, const sffi_func *func, void *rvalue, unsigned int argc, void **argv);
+sffi_error *sffi_adapter_set(const sffi_adapter *adapter, const sffi_struct_type *struct_type, void *structure, unsigned int index, const void *avalue);
+sffi_error *sffi_adapter_get(const sffi_adapter *adapter, const sffi_struct_type *struct_type, const void *structure, unsigned int index, void *rvalue);
+void sffi_adapter_free(sffi_adapter *adapter);
+sffi_error *sffi_adapter_free_checked(sffi_adapter *adapter);
+void sffi_string_free(char *str);
+
+/* Bound adapters, casts resolved once for one function; with handle checking, closing its library invalidates the bound adapter, which must still be freed */
+
+sffi_error *sffi_adapter_bind(sffi_bound_adapter **r_bound, const sffi_adapter *adapter, const sffi_func *func);
+sffi_error *sffi_bound_adapter_call(const sffi_bound_adapter *bound, void *rvalue, unsigned int argc, void **argv);
+void sffi_bound_adapter_free(sffi_bound_adapter *bound);
+sffi_error *sffi_bound_adapter_free_checked(sffi_bound_adapter *bound);
+
+/* Structures */
+
+sffi_error *sffi_struct_type_alloc(sffi_struct_type **r_struct_type, const char *desc);
+unsigned int sffi_struct_type_size(const sffi_struct_type *struct_type);
+sffi_error *sffi_struct_type_size_checked(unsigned int *r_size, const sffi_struct_type *struct_type);
+unsigned int sffi_struct_type_align(const sffi_struct_type *struct_type);
+sffi_error *sffi_struct_type_align_checked(unsigned int *r_align, const sffi_struct_type *struct_type);
+sffi_error *sffi_struct_type_field_count(unsigned int *r_count, const sffi_struct_type *struct_type);
+sffi_error *sffi_struct_type_field(unsigned int *r_kind, unsigned int *r_offset, unsigned int *r_size, unsigned int *r_align, const sffi_struct_type *struct_type, unsigned int index);
+sffi_error *sffi_struct_type_field_struct(sffi_struct_type **r_struct_type, const sffi_struct_type *struct_type, unsigned int index);
+void *sffi_struct_malloc(const sffi_struct_type *struct_type);
+sffi_error *sffi_struct_malloc_checked(void **r_structure, const sffi_struct_type *struct_type);
+void *sffi_struct_calloc(const sffi_struct_type *struct_type);
+sffi_error *sffi_struct_calloc_checked(void **r_structure, const sffi_struct_type *struct_type);
+void sffi_struct_set_raw(const sffi_struct_type *struct_type, void *structure, unsigned int index, const void *avalue);
+sffi_error *sffi_struct_set_raw_checked(const sffi_struct_type *struct_type, void *structure, unsigned int index, const void *avalue);
+void sffi_struct_get_raw(const sffi_struct_type *struct_type, const void *structure, unsigned int index, void *rvalue);
+sffi_error *sffi_struct_get_raw_checked(const sffi_struct_type *struct_type, const void *structure, unsigned int index, void *rvalue);
+
+/* Arrays, the elements are `sffi_struct_type_stride` bytes apart and the array is freed with sffi_struct_free */
+
+unsigned int sffi_struct_type_stride(const sffi_struct_type *struct_type);
+sffi_error *sffi_struct_array_alloc(void **r_array, const sffi_struct_type *struct_type, size_t count);
+sffi_error *sffi_struct_array_element(void **r_element, const sffi_struct_type *struct_type, void *array, size_t count, size_t element);
+sffi_error *sffi_struct_array_set_raw(const sffi_struct_type *struct_type, void *array, size_t count, size_t element, unsigned int index, const void *avalue);
+sffi_error *sffi_struct_array_get_raw(const sffi_struct_type *struct_type, const void *array, size_t count, size_t element, unsigned int index, void *rvalue);
+
+/* Typed field accessors, they fail on an invalid index or a field of another type */
+
+sffi_error *sffi_struct_get_i8(int8_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_i8(const sffi_struct_type *struct_type, void *structure, unsigned int index, int8_t value);
+sffi_error *sffi_struct_get_i16(int16_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_i16(const sffi_struct_type *struct_type, void *structure, unsigned int index, int16_t value);
+sffi_error *sffi_struct_get_i32(int32_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_i32(const sffi_struct_type *struct_type, void *structure, unsigned int index, int32_t value);
+sffi_error *sffi_struct_get_i64(int64_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_i64(const sffi_struct_type *struct_type, void *structure, unsigned int index, int64_t value);
+sffi_error *sffi_struct_get_u8(uint8_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_u8(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint8_t value);
+sffi_error *sffi_struct_get_u16(uint16_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_u16(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint16_t value);
+sffi_error *sffi_struct_get_u32(uint32_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_u32(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint32_t value);
+sffi_error *sffi_struct_get_u64(uint64_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_u64(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint64_t value);
+sffi_error *sffi_struct_get_isize(ptrdiff_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_isize(const sffi_struct_type *struct_type, void *structure, unsigned int index, ptrdiff_t value);
+sffi_error *sffi_struct_get_usize(size_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_usize(const sffi_struct_type *struct_type, void *structure, unsigned int index, size_t value);
+sffi_error *sffi_struct_get_f32(float *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_f32(const sffi_struct_type *struct_type, void *structure, unsigned int index, float value);
+sffi_error *sffi_struct_get_f64(double *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_f64(const sffi_struct_type *struct_type, void *structure, unsigned int index, double value);
+sffi_error *sffi_struct_get_ptr(void **r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+sffi_error *sffi_struct_set_ptr(const sffi_struct_type *struct_type, void *structure, unsigned int index, void *value);
+
+void sffi_struct_free(void *structure);
+void sffi_struct_type_free(sffi_struct_type *struct_type);
+sffi_error *sffi_struct_type_free_checked(sffi_struct_type *struct_type);
+
+/* Status codes, the details of the last failure are kept per thread */
+
+unsigned int sffi_last_error_code(void);
+const char *sffi_last_error_msg(void);
+
+unsigned int sffi_lib_open_status(sffi_lib **r_handle, const char *name);
+unsigned int sffi_lib_open_now_status(sffi_lib **r_handle, const char *name);
+unsigned int sffi_lib_symbol_status(const void **r_symbol, const sffi_lib *handle, const char *name);
+unsigned int sffi_lib_func_status(const sffi_func **r_func, sffi_lib *handle, const char *name, const char *desc);
+unsigned int sffi_lib_func_ex_status(const sffi_func **r_func, sffi_lib *handle, const char *name, const char *desc, unsigned int syntax);
+unsigned int sffi_lib_as_raw_status(void **r_raw, const sffi_lib *handle);
+unsigned int sffi_lib_close_status(sffi_lib *handle);
+
+unsigned int sffi_func_call_status(const sffi_func *func, void *rvalue, void **avalue);
+unsigned int sffi_func_call_raw_status(const sffi_func *func, void *rvalue, void *avalue);
+unsigned int sffi_func_as_raw_status(const void **r_raw, const sffi_func *func);
+unsigned int sffi_func_arg_count_status(unsigned int *r_count, const sffi_func *func);
+unsigned int sffi_func_arg_type_status(unsigned int *r_kind, unsigned int *r_size, unsigned int *r_align, const sffi_func *func, unsigned int index);
+unsigned int sffi_func_ret_type_status(unsigned int *r_kind, unsigned int *r_size, unsigned int *r_align, const sffi_func *func);
+unsigned int sffi_func_arg_struct_status(sffi_struct_type **r_struct_type, const sffi_func *func, unsigned int index);
+unsigned int sffi_func_ret_struct_status(sffi_struct_type **r_struct_type, const sffi_func *func);
+unsigned int sffi_func_desc_status(char **r_desc, const sffi_func *func);
+
+unsigned int sffi_adapter_parse_status(sffi_adapter **r_adapter, const char *desc);
+unsigned int sffi_adapter_set_strict_status(sffi_adapter *adapter, bool strict);
+unsigned int sffi_adapter_set_leaked_status(sffi_adapter *adapter, unsigned int index, bool leaked);
+unsigned int sffi_adapter_call_status(const sffi_adapter *adapter, const sffi_func *func, void *rvalue, unsigned int argc, void **argv);
+unsigned int sffi_adapter_set_status(const sffi_adapter *adapter, const sffi_struct_type *struct_type, void *structure, unsigned int index, const void *avalue);
+unsigned int sffi_adapter_get_status(const sffi_adapter *adapter, const sffi_struct_type *struct_type, const void *structure, unsigned int index, void *rvalue);
+unsigned int sffi_adapter_free_status(sffi_adapter *adapter);
+unsigned int sffi_adapter_bind_status(sffi_bound_adapter **r_bound, const sffi_adapter *adapter, const sffi_func *func);
+unsigned int sffi_bound_adapter_call_status(const sffi_bound_adapter *bound, void *rvalue, unsigned int argc, void **argv);
+unsigned int sffi_bound_adapter_free_status(sffi_bound_adapter *bound);
+
+unsigned int sffi_struct_type_alloc_status(sffi_struct_type **r_struct_type, const char *desc);
+unsigned int sffi_struct_type_size_status(unsigned int *r_size, const sffi_struct_type *struct_type);
+unsigned int sffi_struct_type_align_status(unsigned int *r_align, const sffi_struct_type *struct_type);
+unsigned int sffi_struct_type_field_count_status(unsigned int *r_count, const sffi_struct_type *struct_type);
+unsigned int sffi_struct_type_field_status(unsigned int *r_kind, unsigned int *r_offset, unsigned int *r_size, unsigned int *r_align, const sffi_struct_type *struct_type, unsigned int index);
+unsigned int sffi_struct_type_field_struct_status(sffi_struct_type **r_struct_type, const sffi_struct_type *struct_type, unsigned int index);
+unsigned int sffi_struct_malloc_status(void **r_structure, const sffi_struct_type *struct_type);
+unsigned int sffi_struct_calloc_status(void **r_structure, const sffi_struct_type *struct_type);
+unsigned int sffi_struct_set_raw_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, const void *avalue);
+unsigned int sffi_struct_get_raw_status(const sffi_struct_type *struct_type, const void *structure, unsigned int index, void *rvalue);
+unsigned int sffi_struct_type_free_status(sffi_struct_type *struct_type);
+unsigned int sffi_struct_array_alloc_status(void **r_array, const sffi_struct_type *struct_type, size_t count);
+unsigned int sffi_struct_array_element_status(void **r_element, const sffi_struct_type *struct_type, void *array, size_t count, size_t element);
+unsigned int sffi_struct_array_set_raw_status(const sffi_struct_type *struct_type, void *array, size_t count, size_t element, unsigned int index, const void *avalue);
+unsigned int sffi_struct_array_get_raw_status(const sffi_struct_type *struct_type, const void *array, size_t count, size_t element, unsigned int index, void *rvalue);
+
+unsigned int sffi_struct_get_i8_status(int8_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_i8_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, int8_t value);
+unsigned int sffi_struct_get_i16_status(int16_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_i16_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, int16_t value);
+unsigned int sffi_struct_get_i32_status(int32_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_i32_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, int32_t value);
+unsigned int sffi_struct_get_i64_status(int64_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_i64_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, int64_t value);
+unsigned int sffi_struct_get_u8_status(uint8_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_u8_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint8_t value);
+unsigned int sffi_struct_get_u16_status(uint16_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_u16_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint16_t value);
+unsigned int sffi_struct_get_u32_status(uint32_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_u32_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint32_t value);
+unsigned int sffi_struct_get_u64_status(uint64_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_u64_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, uint64_t value);
+unsigned int sffi_struct_get_isize_status(ptrdiff_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_isize_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, ptrdiff_t value);
+unsigned int sffi_struct_get_usize_status(size_t *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_usize_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, size_t value);
+unsigned int sffi_struct_get_f32_status(float *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_f32_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, float value);
+unsigned int sffi_struct_get_f64_status(double *r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_f64_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, double value);
+unsigned int sffi_struct_get_ptr_status(void **r_value, const sffi_struct_type *struct_type, const void *structure, unsigned int index);
+unsigned int sffi_struct_set_ptr_status(const sffi_struct_type *struct_type, void *structure, unsigned int index, void *value);
+
+unsigned int sffi_set_allocator_status(sffi_alloc_fn alloc, sffi_free_fn free, void *data);
+unsigned int sffi_set_handle_checking_status(bool enabled);
+
+/* Allocator of structures, call arguments and strings, both null restore malloc and free.
+   Handles and errors still use the internal allocator.
+   Every block is freed by the allocator which made it, whose hooks and data must stay valid until then.
+   Since ABI version 2, structures and strings handed out by sffi start after a header, even with the default
+   allocator: free them with sffi_struct_free or sffi_string_free, never with free(), and never pass a block
+   of malloc to those functions. */
+
+sffi_error *sffi_set_allocator(sffi_alloc_fn alloc, sffi_free_fn free, void *data);
+
+/* Handle checking, it can only be switched while no handle is alive */
+
+bool sffi_handle_checking(void);
+sffi_error *sffi_set_handle_checking(bool enabled);
+
+/* Leak tracking */
+
+void sffi_debug_set_leak_tracking(bool enabled);
+unsigned int sffi_debug_live_count(unsigned int resource);
+unsigned int sffi_debug_report_leaks(void);
+
+/* Version */
+
+const char *sffi_version(void);
+unsigned int sffi_abi_version(void);
+bool sffi_has_feature(const char *name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -1,17 +1,21 @@
+use crate::arena;
+use crate::arena::Scope;
+use crate::bound::BoundAdapter;
+use crate::enumeration::{fits, read_integer, write_integer, EnumType};
 use crate::error::Error;
+use crate::flags::FlagsType;
 use crate::interface::{FuncDesc, FuncHandle, FuncType};
-use crate::internal::{ends_with, starts_with};
+use crate::internal::{ends_with, starts_with, try_c_const_char_to_str, try_str_to_c_string};
 use crate::structure::StructType;
-use std::alloc::{alloc, dealloc, Layout};
+use std::alloc::Layout;
 use std::ffi::{c_char, c_double, c_float, c_int, c_uint, c_void};
-use std::ops::{Deref, DerefMut};
-use std::ptr::{read, write};
+use std::ptr::{copy_nonoverlapping, read, write};
 
-pub struct Adapter(FuncDesc);
-
-struct SafeAlloc {
-    layout: Layout,
-    value: *mut c_void
+#[derive(Clone)]
+pub struct Adapter {
+    desc: FuncDesc,
+    strict: bool,
+    leaked: Box<[bool]>,
 }
 
 impl Adapter {
@@ -19,16 +23,48 @@ impl Adapter {
         let str = str.trim_start();
         if starts_with(str, '[') {
             if ends_with(str, ']') {
-                let str = format!("({})void", &str[1..str.len() - 1]);
-                Ok(Self(FuncDesc::from_str(str.as_str())?))
+                let desc = format!("({})void", &str[1..str.len() - 1]);
+                Ok(Self::new(FuncDesc::from_str(desc.as_str()).map_err(|it| it.with_descriptor(str))?))
             } else {
                 Error::invalid_desc_from_str("Invalid descriptor end")
             }
         } else {
-            Ok(Self(FuncDesc::from_str(str)?))
+            Ok(Self::new(FuncDesc::from_str(str)?))
         }
     }
 
+    fn new(desc: FuncDesc) -> Self {
+        let leaked = vec![false; desc.argument_types().len()].into_boxed_slice();
+        Self { desc, strict: true, leaked }
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_leaked(&self, index: c_uint) -> bool {
+        self.leaked.get(index as usize).copied().unwrap_or(false)
+    }
+
+    pub fn set_leaked(&mut self, index: c_uint, leaked: bool) -> Result<(), Error> {
+        let count = self.leaked.len();
+        let Some(it) = self.leaked.get_mut(index as usize) else { return Error::invalid_arg_from_string(index as usize, format!("Invalid index ({} / {})", index, count)) };
+        *it = leaked;
+        Ok(())
+    }
+
+    pub fn desc(&self) -> &FuncDesc {
+        &self.desc
+    }
+
+    pub fn bind<'a>(&self, func: &'a FuncHandle) -> Result<BoundAdapter<'a>, Error> {
+        BoundAdapter::new(self, func)
+    }
+
     pub fn call(&self, func: *const FuncHandle, result: *mut c_void, arguments: &mut [*mut c_void]) -> Result<(), Error> {
         unsafe { self._call(func, result, arguments.len() as c_uint, arguments.as_mut_ptr()) }
     }
@@ -37,25 +73,26 @@ impl Adapter {
         unsafe {
             self._call_check_arguments(func, argc)?;
             
+            let scope = Scope::enter();
             let func_return_type = (*func).desc().return_type();
-            let func_return = SafeAlloc::alloc(func_return_type);
+            let func_return = Self::_call_alloc(&scope, func_return_type)?;
 
             let argc = argc as usize;
-            let mut func_arguments_safe = Vec::with_capacity(argc);
-            let mut func_arguments = Vec::with_capacity(argc);
-            let target_arguments_types = self.0.argument_types();
+            let func_arguments = scope.alloc(argc * size_of::<*mut c_void>(), align_of::<*mut c_void>())? as *mut *mut c_void;
+            let target_arguments_types = self.desc.argument_types();
             let func_arguments_types = (*func).desc().argument_types();
             for i in 0..argc {
                 let func_type = &func_arguments_types[i];
-                let allocation = SafeAlloc::alloc(func_type);
-                Self::_call_cast_type(&target_arguments_types[i], func_type, *argv.add(i), *allocation)?;
-                func_arguments.push(*allocation);
-                func_arguments_safe.push(allocation);
+                let allocation = Self::_call_alloc(&scope, func_type).map_err(|it| it.with_argument(i))?;
+                let cast = || self._call_cast_type(&target_arguments_types[i], func_type, *argv.add(i), allocation);
+                if self.leaked[i] { arena::leaked(cast) } else { scope.temporary(cast) }.map_err(|it| it.with_argument(i))?;
+                *func_arguments.add(i) = allocation;
             }
             
-            (*func)._call(*func_return, func_arguments.as_mut_ptr());
+            (*func)._call(func_return, func_arguments);
 
-            Self::_call_cast_type(func_return_type, self.0.return_type(), *func_return, result)?;
+            // The caller owns the converted return value, strings allocated for it outlive the scope
+            arena::leaked(|| self._call_cast_type(func_return_type, self.desc.return_type(), func_return, result))?;
             
             Ok(())
         }
@@ -68,9 +105,9 @@ impl Adapter {
     pub(crate) unsafe fn _set(&self, struct_type: *const StructType, structure: *mut c_void, index: c_uint, avalue: *const c_void) -> Result<(), Error> {
         unsafe {
             self._access_check_arguments(&*struct_type, index)?;
-            let from_type = self.0.argument_types().get_unchecked(index as usize);
+            let from_type = self.desc.argument_types().get_unchecked(index as usize);
             let (into_type, offset) = (*struct_type).fields().get_unchecked(index as usize);
-            Self::_call_cast_type(from_type, into_type, avalue, structure.byte_offset(*offset as isize))
+            self._call_cast_type(from_type, into_type, avalue, structure.byte_offset(*offset as isize))
         }
     }
 
@@ -81,14 +118,30 @@ impl Adapter {
     pub(crate) unsafe fn _get(&self, struct_type: *const StructType, structure: *const c_void, index: c_uint, rvalue: *mut c_void) -> Result<(), Error> {
         unsafe {
             self._access_check_arguments(&*struct_type, index)?;
-            let into_type = self.0.argument_types().get_unchecked(index as usize);
+            let into_type = self.desc.argument_types().get_unchecked(index as usize);
             let (from_type, offset) = (*struct_type).fields().get_unchecked(index as usize);
-            Self::_call_cast_type(from_type, into_type, structure.byte_offset(*offset as isize), rvalue)
+            self._call_cast_type(from_type, into_type, structure.byte_offset(*offset as isize), rvalue)
         }
     }
 
-    fn _call_cast_type(from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> { // todo: fix long double
+    pub(crate) fn _call_cast_type(&self, from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> { // todo: fix long double
         unsafe {
+            if let FuncType::Enum(enum_type) = into_type && !from_type.is_auto() {
+                return self._cast_into_enum(enum_type, from_type, into_type, from_addr, into_addr)
+            }
+
+            if let FuncType::Enum(enum_type) = from_type && !into_type.is_auto() {
+                return self._cast_from_enum(enum_type, from_type, into_type, from_addr, into_addr)
+            }
+
+            if let FuncType::Flags(flags_type) = into_type && !from_type.is_auto() {
+                return self._cast_into_flags(flags_type, from_type, into_type, from_addr, into_addr)
+            }
+
+            if let FuncType::Flags(flags_type) = from_type && !into_type.is_auto() {
+                return self._cast_from_flags(flags_type, from_type, into_type, from_addr, into_addr)
+            }
+
             if from_type.is_auto() {
                 match into_type {
                     FuncType::Auto => unreachable!(),
@@ -105,23 +158,31 @@ impl Adapter {
                     FuncType::S16               => write::<i16>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::S32               => write::<i32>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::S64               => write::<i64>     (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::S128              => write::<i128>    (into_addr as *mut _, read(from_addr as *const _)),
 
                     FuncType::U8                => write::<u8>      (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::U16               => write::<u16>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::U32               => write::<u32>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::U64               => write::<u64>     (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::U128              => write::<u128>    (into_addr as *mut _, read(from_addr as *const _)),
 
                     FuncType::F32               => write::<f32>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::F64               => write::<f64>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::F128              => write::<i128>    (into_addr as *mut _, read(from_addr as *const _)),
 
+                    FuncType::ComplexF32        => write::<[f32; 2]>    (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::ComplexF64        => write::<[f64; 2]>    (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::ComplexLongDouble => write::<[i128; 2]>   (into_addr as *mut _, read(from_addr as *const _)),
+
                     FuncType::Pointer           => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::RefStringPtr      => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::BorrowStringPtr   => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::RefArrayPtr       => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::BorrowArrayPtr    => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
 
-                    FuncType::Struct(_)         => todo!()
+                    FuncType::Struct(_)         => todo!(),
+                    FuncType::Enum(it)          => copy_nonoverlapping(from_addr as *const u8, into_addr as *mut u8, it.size() as usize),
+                    FuncType::Flags(it)         => copy_nonoverlapping(from_addr as *const u8, into_addr as *mut u8, it.size() as usize)
                 }
                 return Ok(())
             }
@@ -142,31 +203,50 @@ impl Adapter {
                     FuncType::S16               => write::<i16>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::S32               => write::<i32>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::S64               => write::<i64>     (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::S128              => write::<i128>    (into_addr as *mut _, read(from_addr as *const _)),
 
                     FuncType::U8                => write::<u8>      (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::U16               => write::<u16>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::U32               => write::<u32>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::U64               => write::<u64>     (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::U128              => write::<u128>    (into_addr as *mut _, read(from_addr as *const _)),
 
                     FuncType::F32               => write::<f32>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::F64               => write::<f64>     (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::F128              => write::<i128>    (into_addr as *mut _, read(from_addr as *const _)),
 
+                    FuncType::ComplexF32        => write::<[f32; 2]>    (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::ComplexF64        => write::<[f64; 2]>    (into_addr as *mut _, read(from_addr as *const _)),
+                    FuncType::ComplexLongDouble => write::<[i128; 2]>   (into_addr as *mut _, read(from_addr as *const _)),
+
                     FuncType::Pointer           => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::RefStringPtr      => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::BorrowStringPtr   => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::RefArrayPtr       => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
                     FuncType::BorrowArrayPtr    => write::<isize>   (into_addr as *mut _, read(from_addr as *const _)),
 
-                    FuncType::Struct(_)         => todo!()
+                    FuncType::Struct(_)         => todo!(),
+                    FuncType::Enum(it)          => copy_nonoverlapping(from_addr as *const u8, into_addr as *mut u8, it.size() as usize),
+                    FuncType::Flags(it)         => copy_nonoverlapping(from_addr as *const u8, into_addr as *mut u8, it.size() as usize)
                 }
                 return Ok(())
             }
 
+            if from_type.is_void() && into_type.is_void() {
+                return Ok(())
+            }
+
+            if from_type.is_complex() || into_type.is_complex() {
+                return self._cast_complex(from_type, into_type, from_addr, into_addr)
+            }
+
+            if from_type.is_int128() || into_type.is_int128() {
+                return self._cast_int128(from_type, into_type, from_addr, into_addr)
+            }
+
             if from_type.is_ptr() && into_type.is_ptr() {
                 if from_type.is_ref_str() && into_type.is_borrow_str() {
-                    unsafe extern "C" { fn strdup(str: *const c_char) -> *mut c_char; }
-                    write::<*const c_char>(into_addr as *mut _, strdup(read(from_addr as *const _)));
+                    write::<*const c_char>(into_addr as *mut _, arena::strdup(read(from_addr as *const _)));
                 } else {
                     write::<isize>(into_addr as *mut _, read(from_addr as *const _));
                 }
@@ -207,7 +287,7 @@ impl Adapter {
                     FuncType::F64       => write::<f64>         (into_addr as *mut _, value as _),
                     FuncType::F128      => write::<i128>        (into_addr as *mut _, value as _),
 
-                    _ => return Error::invalid_cast_from_string(format!("Invalid cast from '{}' into '{}'", from_type, into_type)),
+                    _ => return Error::invalid_cast(from_type, into_type),
                 }
                 return Ok(())
             }
@@ -245,7 +325,7 @@ impl Adapter {
                     FuncType::F64       => write::<f64>         (into_addr as *mut _, value as _),
                     FuncType::F128      => write::<i128>        (into_addr as *mut _, value as _),
 
-                    _ => return Error::invalid_cast_from_string(format!("Invalid cast from '{}' into '{}'", from_type, into_type)),
+                    _ => return Error::invalid_cast(from_type, into_type),
                 }
                 return Ok(())
             }
@@ -284,16 +364,182 @@ impl Adapter {
                     FuncType::F64       => write::<f64>         (into_addr as *mut _, value as _),
                     FuncType::F128      => write::<i128>        (into_addr as *mut _, value as _),
 
-                    _ => return Error::invalid_cast_from_string(format!("Invalid cast from '{}' into '{}'", from_type, into_type)),
+                    _ => return Error::invalid_cast(from_type, into_type),
                 }
                 return Ok(())
             }
 
-            Error::invalid_cast_from_string(format!("Cast from '{}' into '{}' unsupported", from_type, into_type))
+            Error::invalid_cast(from_type, into_type)
+        }
+    }
+
+    unsafe fn _cast_into_enum(&self, enum_type: &EnumType, from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> {
+        unsafe {
+            if from_type.is_ref_str() || from_type.is_borrow_str() {
+                let name = read(from_addr as *const *const c_char);
+                let name = if name.is_null() { None } else { try_c_const_char_to_str(name) };
+                let value = if let Some(name) = name { enum_type.value_of(name) } else { None };
+                let value = if let Some(value) = value { value } else { return Error::invalid_cast_from_string(from_type, into_type, format!("Unknown name: {:?}", name)) };
+                write_integer(enum_type.repr(), into_addr, value);
+                return Ok(())
+            }
+            let mut buffer = 0u128;
+            let buffer = &mut buffer as *mut u128 as *mut c_void;
+            self._call_cast_type(from_type, enum_type.repr(), from_addr, buffer)?;
+            let value = read_integer(enum_type.repr(), buffer).unwrap_unchecked();
+            if self.strict && !enum_type.contains(value) { return Error::invalid_cast_from_string(from_type, into_type, format!("Value {} is not a member", value)) }
+            write_integer(enum_type.repr(), into_addr, value);
+            Ok(())
+        }
+    }
+
+    unsafe fn _cast_from_enum(&self, enum_type: &EnumType, from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> {
+        unsafe {
+            let value = read_integer(enum_type.repr(), from_addr).unwrap_unchecked();
+            if into_type.is_ref_str() || into_type.is_borrow_str() {
+                let name = if let Some(name) = enum_type.name_of_c(value) { name } else { return Error::invalid_cast_from_string(from_type, into_type, format!("Value {} has no name", value)) };
+                if into_type.is_ref_str() {
+                    write::<*const c_char>(into_addr as *mut _, name.as_ptr());
+                } else {
+                    write::<*mut c_char>(into_addr as *mut _, arena::strdup(name.as_ptr()));
+                }
+                return Ok(())
+            }
+            if self.strict && !enum_type.contains(value) { return Error::invalid_cast_from_string(from_type, into_type, format!("Value {} is not a member", value)) }
+            self._call_cast_type(enum_type.repr(), into_type, from_addr, into_addr)
+        }
+    }
+
+    unsafe fn _cast_into_flags(&self, flags_type: &FlagsType, from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> {
+        unsafe {
+            let value = if from_type.is_ref_str() || from_type.is_borrow_str() {
+                let names = read(from_addr as *const *const c_char);
+                let names = if names.is_null() { None } else { try_c_const_char_to_str(names) };
+                let names = if let Some(names) = names { names } else { return Error::invalid_cast_from_string(from_type, into_type, "Invalid flag names".into()) };
+                flags_type.encode_str(names)?
+            } else {
+                let mut buffer = 0u128;
+                let buffer = &mut buffer as *mut u128 as *mut c_void;
+                self._call_cast_type(from_type, flags_type.repr(), from_addr, buffer)?;
+                read_integer(flags_type.repr(), buffer).unwrap_unchecked()
+            };
+            if self.strict && value & !flags_type.mask() != 0 { return Error::invalid_cast_from_string(from_type, into_type, format!("Value {:#x} has unknown bits", value)) }
+            write_integer(flags_type.repr(), into_addr, value);
+            Ok(())
+        }
+    }
+
+    unsafe fn _cast_from_flags(&self, flags_type: &FlagsType, from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> {
+        unsafe {
+            let value = read_integer(flags_type.repr(), from_addr).unwrap_unchecked();
+            if into_type.is_borrow_str() {
+                let names = try_str_to_c_string(&flags_type.decode_str(value))?;
+                write::<*mut c_char>(into_addr as *mut _, arena::strdup(names.as_ptr()));
+                return Ok(())
+            }
+            if into_type.is_ref_str() { return Error::invalid_cast_from_string(from_type, into_type, "Cast allocates, use '*str' instead of '&str'".into()) }
+            if self.strict && value & !flags_type.mask() != 0 { return Error::invalid_cast_from_string(from_type, into_type, format!("Value {:#x} has unknown bits", value)) }
+            self._call_cast_type(flags_type.repr(), into_type, from_addr, into_addr)
+        }
+    }
+
+    unsafe fn _cast_complex(&self, from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> {
+        unsafe {
+            if from_type == into_type {
+                copy_nonoverlapping(from_addr as *const u8, into_addr as *mut u8, from_type.size() as usize);
+                return Ok(())
+            }
+
+            let (re, im): (f64, f64) =
+                match from_type {
+                    FuncType::ComplexF32 => { let [re, im] = read::<[f32; 2]>(from_addr as *const _); (re as _, im as _) }
+                    FuncType::ComplexF64 => { let [re, im] = read::<[f64; 2]>(from_addr as *const _); (re, im) }
+                    FuncType::Struct(structure) if Self::_is_complex_pair(structure) => {
+                        let mut pair = [0f64; 2];
+                        for (i, (field, offset)) in structure.fields().iter().enumerate() {
+                            self._call_cast_type(field, &FuncType::F64, from_addr.byte_offset(*offset as isize), &mut pair[i] as *mut f64 as *mut c_void)?;
+                        }
+                        (pair[0], pair[1])
+                    }
+                    _ if from_type.is_integer() || from_type.is_int128() || from_type.is_float() => {
+                        let mut re = 0f64;
+                        self._call_cast_type(from_type, &FuncType::F64, from_addr, &mut re as *mut f64 as *mut c_void)?;
+                        (re, 0.0)
+                    }
+                    _ => return Error::invalid_cast(from_type, into_type),
+                };
+
+            match into_type {
+                FuncType::ComplexF32 => write::<[f32; 2]>(into_addr as *mut _, [re as _, im as _]),
+                FuncType::ComplexF64 => write::<[f64; 2]>(into_addr as *mut _, [re, im]),
+                FuncType::Struct(structure) if Self::_is_complex_pair(structure) => {
+                    let pair = [re, im];
+                    for (i, (field, offset)) in structure.fields().iter().enumerate() {
+                        self._call_cast_type(&FuncType::F64, field, &pair[i] as *const f64 as *const c_void, into_addr.byte_offset(*offset as isize))?;
+                    }
+                }
+                _ => return Error::invalid_cast(from_type, into_type),
+            }
+            Ok(())
+        }
+    }
+
+    unsafe fn _cast_int128(&self, from_type: &FuncType, into_type: &FuncType, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> {
+        enum Wide { Signed(i128), Unsigned(u128), Float(f64) }
+
+        unsafe {
+            let value =
+                match from_type {
+                    FuncType::S128                      => Wide::Signed(read::<i128>(from_addr as *const _)),
+                    FuncType::U128                      => Wide::Unsigned(read::<u128>(from_addr as *const _)),
+                    FuncType::Float | FuncType::F32     => Wide::Float(read::<c_float>(from_addr as *const _) as _),
+                    FuncType::Double | FuncType::F64    => Wide::Float(read::<c_double>(from_addr as *const _)),
+                    _ if from_type.is_integer()         => Wide::Signed(read_integer(from_type, from_addr).unwrap_unchecked()),
+                    _ => return Error::invalid_cast(from_type, into_type),
+                };
+
+            let overflow = || Error::invalid_cast_from_string(from_type, into_type, "Value overflows".into());
+            match into_type {
+                FuncType::Float | FuncType::F32 => write::<c_float>(into_addr as *mut _, match value { Wide::Signed(it) => it as _, Wide::Unsigned(it) => it as _, Wide::Float(it) => it as _ }),
+                FuncType::Double | FuncType::F64 => write::<c_double>(into_addr as *mut _, match value { Wide::Signed(it) => it as _, Wide::Unsigned(it) => it as _, Wide::Float(it) => it }),
+                FuncType::U128 => {
+                    let value = match value {
+                        Wide::Signed(it) => if let Ok(it) = u128::try_from(it) { it } else { return overflow() },
+                        Wide::Unsigned(it) => it,
+                        Wide::Float(it) => if it > -1.0 && it < u128::MAX as f64 { it as _ } else { return overflow() },
+                    };
+                    write::<u128>(into_addr as *mut _, value)
+                }
+                _ => {
+                    let value = match value {
+                        Wide::Signed(it) => it,
+                        Wide::Unsigned(it) => if let Ok(it) = i128::try_from(it) { it } else { return overflow() },
+                        Wide::Float(it) => if it >= i128::MIN as f64 && it < i128::MAX as f64 { it as _ } else { return overflow() },
+                    };
+                    match into_type {
+                        FuncType::S128 => write::<i128>(into_addr as *mut _, value),
+                        _ if into_type.is_integer() => {
+                            if !fits(into_type, value) { return overflow() }
+                            write_integer(into_type, into_addr, value)
+                        }
+                        _ => return Error::invalid_cast(from_type, into_type),
+                    }
+                }
+            }
+            Ok(())
         }
     }
 
-    fn _call_calc_type_layout(r#type: &FuncType) -> Layout {
+    pub(crate) fn _is_complex_pair(structure: &StructType) -> bool {
+        structure.fields().len() == 2 && structure.fields().iter().all(|(field, _)| field.is_float())
+    }
+
+    fn _call_alloc(scope: &Scope, r#type: &FuncType) -> Result<*mut c_void, Error> {
+        let layout = Self::_call_calc_type_layout(r#type);
+        scope.alloc(layout.size(), layout.align())
+    }
+
+    pub(crate) fn _call_calc_type_layout(r#type: &FuncType) -> Layout {
         match r#type {
             FuncType::Auto              => unreachable!(),
             FuncType::Void              => unsafe { Layout::array::<u8>(0).unwrap_unchecked() },
@@ -309,28 +555,36 @@ impl Adapter {
             FuncType::S16               => Layout::new::<i16>(),
             FuncType::S32               => Layout::new::<i32>(),
             FuncType::S64               => Layout::new::<i64>(),
+            FuncType::S128              => Layout::new::<i128>(),
 
             FuncType::U8                => Layout::new::<u8>(),
             FuncType::U16               => Layout::new::<u16>(),
             FuncType::U32               => Layout::new::<u32>(),
             FuncType::U64               => Layout::new::<u64>(),
+            FuncType::U128              => Layout::new::<u128>(),
 
             FuncType::F32               => Layout::new::<f32>(),
             FuncType::F64               => Layout::new::<f64>(),
             FuncType::F128              => Layout::new::<i128>(),
 
+            FuncType::ComplexF32        => Layout::new::<[f32; 2]>(),
+            FuncType::ComplexF64        => Layout::new::<[f64; 2]>(),
+            FuncType::ComplexLongDouble => Layout::new::<[i128; 2]>(),
+
             FuncType::Pointer           => Layout::new::<*mut c_void>(),
             FuncType::RefStringPtr      => Layout::new::<*mut c_void>(),
             FuncType::BorrowStringPtr   => Layout::new::<*mut c_void>(),
             FuncType::RefArrayPtr       => Layout::new::<*mut c_void>(),
             FuncType::BorrowArrayPtr    => Layout::new::<*mut c_void>(),
 
-            FuncType::Struct(it)         => unsafe { Layout::array::<u8>(it.size() as usize).unwrap_unchecked() }
+            FuncType::Struct(it)         => unsafe { Layout::array::<u8>(it.size() as usize).unwrap_unchecked() },
+            FuncType::Enum(it)           => Self::_call_calc_type_layout(it.repr()),
+            FuncType::Flags(it)          => Self::_call_calc_type_layout(it.repr())
         }
     }
 
     fn _call_check_arguments(&self, func: *const FuncHandle, argc: c_uint) -> Result<(), Error> {
-        let target_argc = self.0.argument_types().len();
+        let target_argc = self.desc.argument_types().len();
         let func_argc = unsafe { (*func).desc().argument_types().len() };
         if target_argc != func_argc { return Error::invalid_args_from_string(format!("Function invalid arguments count ({} / {})", func_argc, target_argc)); }
         let accepted_argc = argc as usize;
@@ -339,42 +593,113 @@ impl Adapter {
     }
 
     fn _access_check_arguments(&self, struct_type: &StructType, index: c_uint) -> Result<(), Error> {
-        let target_count = self.0.argument_types().len();
-        if target_count <= index as usize { return Error::invalid_args_from_string(format!("Invalid index ({} / {})", index, target_count)); }
+        let target_count = self.desc.argument_types().len();
+        if target_count <= index as usize { return Error::invalid_arg_from_string(index as usize, format!("Invalid index ({} / {})", index, target_count)); }
         let struct_count = struct_type.fields().len();
         if target_count != struct_count { return Error::invalid_args_from_string(format!("Structure invalid fields count ({} / {})", struct_count, target_count)); }
         Ok(())
     }
 }
 
-impl SafeAlloc {
-    fn alloc(r#type: &FuncType) -> Self {
-        unsafe {
-            let layout = Adapter::_call_calc_type_layout(r#type);
-            Self {
-                layout,
-                value: alloc(layout) as *mut c_void,
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complex() {
+        let struct_type = StructType::from_str("[cf64,cf32]").unwrap();
+        let mut structure = [0u64; 3];
+        let structure = &mut structure as *mut [u64; 3] as *mut c_void;
+
+        let adapter = Adapter::from_str("[[f32,f32],i32]").unwrap();
+        adapter.set(&struct_type, structure, 0, &[1.5f32, 0f32] as *const [f32; 2] as *const c_void).unwrap();
+        adapter.set(&struct_type, structure, 1, &7i32 as *const i32 as *const c_void).unwrap();
+
+        let adapter = Adapter::from_str("[cf32,cf64]").unwrap();
+        let mut first = [0f32; 2];
+        let mut second = [0f64; 2];
+        adapter.get(&struct_type, structure, 0, &mut first as *mut [f32; 2] as *mut c_void).unwrap();
+        adapter.get(&struct_type, structure, 1, &mut second as *mut [f64; 2] as *mut c_void).unwrap();
+        assert_eq!(first, [1.5, 0.0]);
+        assert_eq!(second, [7.0, 0.0]);
+
+        let adapter = Adapter::from_str("[f64,f64]").unwrap();
+        let mut result = 0f64;
+        assert!(adapter.get(&struct_type, structure, 0, &mut result as *mut f64 as *mut c_void).is_err());
     }
-}
 
-impl Drop for SafeAlloc {
-    fn drop(&mut self) {
-        unsafe { dealloc(self.value as *mut u8, self.layout) }
+    #[test]
+    fn test_int128() {
+        let struct_type = StructType::from_str("[i128,u128,i8]").unwrap();
+        let mut structure = [0u128; 3];
+        let structure = &mut structure as *mut [u128; 3] as *mut c_void;
+
+        let adapter = Adapter::from_str("[i64,f64,u64]").unwrap();
+        adapter.set(&struct_type, structure, 0, &-5i64 as *const i64 as *const c_void).unwrap();
+        adapter.set(&struct_type, structure, 1, &1e30f64 as *const f64 as *const c_void).unwrap();
+        adapter.set(&struct_type, structure, 2, &100u64 as *const u64 as *const c_void).unwrap();
+        assert!(adapter.set(&struct_type, structure, 1, &-1f64 as *const f64 as *const c_void).is_err());
+
+        let adapter = Adapter::from_str("[u128,i128,i128]").unwrap();
+        let mut result = 0i128;
+        assert!(adapter.get(&struct_type, structure, 0, &mut result as *mut i128 as *mut c_void).is_err());
+        adapter.get(&struct_type, structure, 1, &mut result as *mut i128 as *mut c_void).unwrap();
+        assert_eq!(result, 1e30f64 as i128);
+        adapter.get(&struct_type, structure, 2, &mut result as *mut i128 as *mut c_void).unwrap();
+        assert_eq!(result, 100);
+        assert!(adapter.set(&struct_type, structure, 2, &1000i128 as *const i128 as *const c_void).is_err());
+
+        let adapter = Adapter::from_str("[i32,i8,i64]").unwrap();
+        let mut result = 0i8;
+        assert!(adapter.get(&struct_type, structure, 1, &mut result as *mut i8 as *mut c_void).is_err());
     }
-}
-
-impl Deref for SafeAlloc {
-    type Target = *mut c_void;
 
-    fn deref(&self) -> &Self::Target {
-        &self.value
+    #[test]
+    fn test_temporaries() {
+        // Next free arena position, the scope is released right away
+        fn mark() -> usize { Scope::enter().alloc(1, 1).unwrap() as usize }
+        static mut INSIDE: (usize, usize) = (0, 0);
+        unsafe extern "C" fn capitalize(text: *mut c_char) {
+            unsafe {
+                *text = b'T' as c_char;
+                INSIDE = (text as usize, mark());
+            }
+        }
+        let func = FuncHandle::new(capitalize as *const c_void, FuncDesc::from_str("(*str)void").unwrap()).unwrap();
+        let adapter = Adapter::from_str("(&str)auto").unwrap();
+        let text = c"text";
+
+        // The copy is taken from the arena during the call and released with it
+        let before = mark();
+        adapter.call(&func, std::ptr::null_mut(), &mut [&text.as_ptr() as *const *const c_char as *mut c_void]).unwrap();
+        let (copy, inside) = unsafe { INSIDE };
+        assert_ne!(copy, text.as_ptr() as usize);
+        assert_ne!(inside, before);
+        assert_eq!(mark(), before);
+        assert_eq!(text, c"text");
     }
-}
 
-impl DerefMut for SafeAlloc {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.value
+    #[test]
+    fn test_leaked() {
+        static mut KEPT: *const c_char = std::ptr::null();
+        unsafe extern "C" fn keep(text: *const c_char) { unsafe { KEPT = text } }
+        let func = FuncHandle::new(keep as *const c_void, FuncDesc::from_str("(*str)void").unwrap()).unwrap();
+        let mut adapter = Adapter::from_str("(&str)auto").unwrap();
+        let call = |adapter: &Adapter, text: &std::ffi::CStr| unsafe {
+            adapter.call(&func, std::ptr::null_mut(), &mut [&text.as_ptr() as *const *const c_char as *mut c_void]).unwrap();
+            KEPT
+        };
+
+        assert!(adapter.set_leaked(1, true).is_err());
+        adapter.set_leaked(0, true).unwrap();
+        assert!(adapter.is_leaked(0));
+
+        // The kept copy is not released with the temporaries of the call
+        let kept = call(&adapter, c"kept");
+        adapter.set_leaked(0, false).unwrap();
+        let temporary = call(&adapter, c"next");
+        assert_ne!(kept, temporary);
+        assert_eq!(unsafe { std::ffi::CStr::from_ptr(kept) }, c"kept");
+        unsafe { crate::api::adapter::sffi_string_free(kept as *mut c_char) }
     }
-}
\ No newline at end of file
+}
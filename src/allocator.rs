@@ -0,0 +1,160 @@
+use crate::error::Error;
+use std::cmp::max;
+use std::ffi::c_void;
+use std::ptr::write_bytes;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Allocator {
+    pub alloc: unsafe extern "C" fn(size: usize, align: usize, data: *mut c_void) -> *mut c_void,
+    pub free: unsafe extern "C" fn(ptr: *mut c_void, data: *mut c_void),
+    pub data: *mut c_void,
+}
+
+// The data pointer is only handed back to the hooks, which must be thread safe.
+unsafe impl Send for Allocator {}
+unsafe impl Sync for Allocator {}
+
+static ALLOCATOR: RwLock<Option<Allocator>> = RwLock::new(None);
+
+mod libc {
+    use std::ffi::c_void;
+
+    unsafe extern "C" {
+        pub fn malloc(size: usize) -> *mut c_void;
+        pub fn free(ptr: *mut c_void);
+    }
+}
+
+pub fn set_allocator(allocator: Option<Allocator>) {
+    *ALLOCATOR.write().unwrap_or_else(|it| it.into_inner()) = allocator;
+}
+
+struct Header {
+    base: *mut c_void,
+    allocator: Option<Allocator>,
+}
+
+const HEADER: usize = size_of::<Header>().next_power_of_two();
+
+pub fn alloc(size: usize, align: usize) -> Result<*mut c_void, Error> {
+    let error = || Error::Allocation { size, align };
+    let offset = max(align, HEADER);
+    let total = max(size, 1).checked_add(offset).ok_or_else(error)?;
+    let allocator = *ALLOCATOR.read().unwrap_or_else(|it| it.into_inner());
+    let base = unsafe {
+        match allocator {
+            Some(allocator) => (allocator.alloc)(total, max(align, align_of::<Header>()), allocator.data),
+            None => libc::malloc(total),
+        }
+    };
+    if base.is_null() { return Err(error()) }
+    if !(base as usize).is_multiple_of(max(align, align_of::<Header>())) {
+        unsafe { dealloc(base, allocator) }
+        return Err(error())
+    }
+    unsafe {
+        let ptr = base.byte_add(offset);
+        (ptr as *mut Header).sub(1).write(Header { base, allocator });
+        Ok(ptr)
+    }
+}
+
+pub fn alloc_zeroed(size: usize, align: usize) -> Result<*mut c_void, Error> {
+    let ptr = alloc(size, align)?;
+    unsafe { write_bytes(ptr as *mut u8, 0, max(size, 1)) }
+    Ok(ptr)
+}
+
+pub fn free(ptr: *mut c_void) {
+    if ptr.is_null() { return }
+    unsafe {
+        let Header { base, allocator } = (ptr as *const Header).sub(1).read();
+        dealloc(base, allocator)
+    }
+}
+
+unsafe fn dealloc(ptr: *mut c_void, allocator: Option<Allocator>) {
+    unsafe {
+        match allocator {
+            Some(allocator) => (allocator.free)(ptr, allocator.data),
+            None => libc::free(ptr),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::api::TEST_LOCK;
+    use std::cell::Cell;
+    use std::sync::MutexGuard;
+
+    // Tests run in parallel, so the hooks delegate to libc and only count or fail on the current thread
+    thread_local! {
+        pub(crate) static COUNT: Cell<isize> = const { Cell::new(0) };
+        pub(crate) static FAIL: Cell<bool> = const { Cell::new(false) };
+    }
+
+    pub(crate) unsafe extern "C" fn counting_alloc(size: usize, _align: usize, _data: *mut c_void) -> *mut c_void {
+        if FAIL.with(Cell::get) { return std::ptr::null_mut() }
+        COUNT.with(|it| it.set(it.get() + 1));
+        unsafe { libc::malloc(size) }
+    }
+
+    pub(crate) unsafe extern "C" fn counting_free(ptr: *mut c_void, _data: *mut c_void) {
+        COUNT.with(|it| it.set(it.get() - 1));
+        unsafe { libc::free(ptr) }
+    }
+
+    pub(crate) const COUNTING: Allocator = Allocator { alloc: counting_alloc, free: counting_free, data: std::ptr::null_mut() };
+
+    pub(crate) struct Installed {
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl Installed {
+        pub(crate) fn new(allocator: Option<Allocator>) -> Self {
+            let lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+            set_allocator(allocator);
+            Self { _lock: lock }
+        }
+    }
+
+    impl Drop for Installed {
+        fn drop(&mut self) {
+            FAIL.with(|it| it.set(false));
+            set_allocator(None);
+        }
+    }
+
+    #[test]
+    fn test_allocator() {
+        let _installed = Installed::new(Some(COUNTING));
+        let ptr = alloc_zeroed(24, 8).unwrap();
+        assert_eq!(unsafe { (ptr as *const [u64; 3]).read() }, [0; 3]);
+        assert_eq!(COUNT.with(Cell::get), 1);
+        free(ptr);
+        assert_eq!(COUNT.with(Cell::get), 0);
+
+        FAIL.with(|it| it.set(true));
+        assert!(matches!(alloc(8, 8), Err(Error::Allocation { size: 8, align: 8 })));
+        FAIL.with(|it| it.set(false));
+    }
+
+    #[test]
+    fn test_swap() {
+        let _installed = Installed::new(Some(COUNTING));
+        let ptr = alloc(16, 8).unwrap();
+        set_allocator(None);
+        let other = alloc(16, 8).unwrap();
+        assert_eq!(COUNT.with(Cell::get), 1);
+
+        // Each block goes back to the allocator which made it
+        free(ptr);
+        assert_eq!(COUNT.with(Cell::get), 0);
+        set_allocator(Some(COUNTING));
+        free(other);
+        assert_eq!(COUNT.with(Cell::get), 0);
+    }
+}
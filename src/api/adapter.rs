@@ -1,5 +1,8 @@
 use crate::adapter::Adapter;
-use crate::api::error::CError;
+use crate::api::error::{guard, guard_or, CError};
+use crate::tracking::{track, untrack, Resource};
+use crate::api::handle::{register, register_in, release, resolve, Kind};
+use crate::bound::BoundAdapter;
 use crate::error::Error;
 use crate::interface::FuncHandle;
 use crate::internal::try_c_const_char_to_str;
@@ -11,56 +14,257 @@ use std::ptr::{drop_in_place, null_mut};
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_adapter_parse(r_adapter: *mut *mut Adapter, desc: *const c_char) -> *mut CError {
-    unsafe {
+    guard("sffi_adapter_parse", || unsafe {
+        check_null!(r_adapter, desc);
         let desc = try_c_const_char_to_str(desc);
-        let desc = if let Some(desc) = desc { desc } else { return Error::InvalidDescriptor(Some("Invalid adapter descriptor".into())).into(); };
+        let desc = if let Some(desc) = desc { desc } else { return Error::InvalidDescriptor { descriptor: None, message: Some("Invalid adapter descriptor".into()) }.into(); };
         match Adapter::from_str(desc) {
             Ok(adapter_) => {
                 let adapter = alloc(Layout::new::<Adapter>()) as *mut Adapter;
                 adapter.copy_from_nonoverlapping(&adapter_, 1);
                 forget(adapter_);
-                *r_adapter = adapter;
+                track(Resource::Adapter, adapter as *const c_void);
+                *r_adapter = register(Kind::Adapter, adapter);
                 null_mut()
             }
             Err(error) => error.into()
         }
-    }
+    })
+}
+
+/// # Safety
+///
+/// `adapter` must be NULL or an adapter returned by `sffi_adapter_parse` that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_adapter_set_strict(adapter: *mut Adapter, strict: bool) {
+    guard_or("sffi_adapter_set_strict", (), || unsafe {
+        if adapter.is_null() { return }
+        let Ok(adapter) = resolve(Kind::Adapter, adapter) else { return };
+        (*adapter).set_strict(strict)
+    })
+}
+
+/// # Safety
+///
+/// `adapter` must be an adapter returned by `sffi_adapter_parse` that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_adapter_set_strict_checked(adapter: *mut Adapter, strict: bool) -> *mut CError {
+    guard("sffi_adapter_set_strict_checked", || unsafe {
+        check_null!(adapter);
+        let adapter = resolve!(Adapter, adapter);
+        (*adapter).set_strict(strict);
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `adapter` must be an adapter returned by `sffi_adapter_parse` that has not been freed and is not in use by another thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_adapter_set_leaked(adapter: *mut Adapter, index: c_uint, leaked: bool) -> *mut CError {
+    guard("sffi_adapter_set_leaked", || unsafe {
+        check_null!(adapter);
+        let adapter = resolve!(Adapter, adapter);
+        match (*adapter).set_leaked(index, leaked) {
+            Ok(_) => null_mut(),
+            Err(error) => error.into()
+        }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_adapter_call(adapter: *const Adapter, func: *const FuncHandle, rvalue: *mut c_void, argc: c_uint, argv: *mut *mut c_void) -> *mut CError {
-    unsafe {
+    guard("sffi_adapter_call", || unsafe {
+        check_null!(adapter, func);
+        if argc > 0 { check_null!(argv); }
+        let (adapter, func) = (resolve!(Adapter, adapter), resolve!(Func, func));
         match (*adapter)._call(func, rvalue, argc, argv) {
             Ok(_) => null_mut(),
             Err(error) => error.into()
         }
-    }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_adapter_set(adapter: *const Adapter, struct_type: *const StructType, structure: *mut c_void, index: c_uint, avalue: *const c_void) -> *mut CError {
-    unsafe {
+    guard("sffi_adapter_set", || unsafe {
+        check_null!(adapter, struct_type, structure, avalue);
+        let (adapter, struct_type) = (resolve!(Adapter, adapter), resolve!(StructType, struct_type));
         match (*adapter)._set(struct_type, structure, index, avalue) {
             Ok(_) => null_mut(),
             Err(error) => error.into()
         }
-    }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_adapter_get(adapter: *const Adapter, struct_type: *const StructType, structure: *const c_void, index: c_uint, rvalue: *mut c_void) -> *mut CError {
-    unsafe {
+    guard("sffi_adapter_get", || unsafe {
+        check_null!(adapter, struct_type, structure, rvalue);
+        let (adapter, struct_type) = (resolve!(Adapter, adapter), resolve!(StructType, struct_type));
         match (*adapter)._get(struct_type, structure, index, rvalue) {
             Ok(_) => null_mut(),
             Err(error) => error.into()
         }
-    }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_adapter_free(adapter: *mut Adapter) {
+    guard_or("sffi_adapter_free", (), || unsafe {
+        if adapter.is_null() { return }
+        if let Ok(adapter) = release(Kind::Adapter, adapter) { drop_adapter(adapter) }
+    })
+}
+
+/// # Safety
+///
+/// `adapter` must come from `sffi_adapter_parse` and must not be used again once this returns successfully.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_adapter_free_checked(adapter: *mut Adapter) -> *mut CError {
+    guard("sffi_adapter_free_checked", || unsafe {
+        check_null!(adapter);
+        match release(Kind::Adapter, adapter) {
+            Ok(adapter) => { drop_adapter(adapter); null_mut() },
+            Err(error) => error.into()
+        }
+    })
+}
+
+/// # Safety
+///
+/// `r_bound` must be writable, `adapter` must outlive the bound adapter and `func` must be a live function handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_adapter_bind(r_bound: *mut *mut BoundAdapter<'static>, adapter: *const Adapter, func: *const FuncHandle) -> *mut CError {
+    guard("sffi_adapter_bind", || unsafe {
+        check_null!(r_bound, adapter, func);
+        let (adapter, target) = (resolve!(Adapter, adapter), resolve!(Func, func));
+        match (*adapter).bind(&*target) {
+            Ok(bound_) => {
+                let bound = alloc(Layout::new::<BoundAdapter>()) as *mut BoundAdapter;
+                bound.copy_from_nonoverlapping(&bound_, 1);
+                forget(bound_);
+                match register_in(Kind::BoundAdapter, bound, Kind::Func, func) {
+                    Ok(handle) => {
+                        track(Resource::BoundAdapter, bound as *const c_void);
+                        *r_bound = handle;
+                        null_mut()
+                    }
+                    Err(error) => { drop_bound_adapter(bound); error.into() }
+                }
+            }
+            Err(error) => error.into()
+        }
+    })
+}
+
+/// # Safety
+///
+/// `bound` must be live, `rvalue` must have room for the adapted return value and `argv` must hold `argc` pointers to adapted arguments.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_bound_adapter_call(bound: *const BoundAdapter<'static>, rvalue: *mut c_void, argc: c_uint, argv: *mut *mut c_void) -> *mut CError {
+    guard("sffi_bound_adapter_call", || unsafe {
+        check_null!(bound);
+        if argc > 0 { check_null!(argv); }
+        let bound = resolve!(BoundAdapter, bound);
+        match (*bound)._call(rvalue, argc, argv) {
+            Ok(_) => null_mut(),
+            Err(error) => error.into()
+        }
+    })
+}
+
+/// # Safety
+///
+/// `bound` must be NULL or come from `sffi_adapter_bind` and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_bound_adapter_free(bound: *mut BoundAdapter<'static>) {
+    guard_or("sffi_bound_adapter_free", (), || unsafe {
+        if bound.is_null() { return }
+        if let Ok(bound) = release(Kind::BoundAdapter, bound) { drop_bound_adapter(bound) }
+    })
+}
+
+/// # Safety
+///
+/// `bound` must come from `sffi_adapter_bind` and must not be used again once this returns successfully.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_bound_adapter_free_checked(bound: *mut BoundAdapter<'static>) -> *mut CError {
+    guard("sffi_bound_adapter_free_checked", || unsafe {
+        check_null!(bound);
+        match release(Kind::BoundAdapter, bound) {
+            Ok(bound) => { drop_bound_adapter(bound); null_mut() },
+            Err(error) => error.into()
+        }
+    })
+}
+
+/// # Safety
+///
+/// `str` must be NULL or a string allocated by this library, such as one from `sffi_func_desc` or a leaked argument, and is freed at most once.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_string_free(str: *mut c_char) {
+    guard_or("sffi_string_free", (), || {
+        untrack(str as *const c_void);
+        StructType::free(str as *mut c_void)
+    })
+}
+
+unsafe fn drop_adapter(adapter: *mut Adapter) {
     unsafe {
+        untrack(adapter as *const c_void);
         drop_in_place(adapter);
         dealloc(adapter as *mut u8, Layout::new::<Adapter>());
     }
-}
\ No newline at end of file
+}
+
+unsafe fn drop_bound_adapter(bound: *mut BoundAdapter) {
+    unsafe {
+        untrack(bound as *const c_void);
+        drop_in_place(bound);
+        dealloc(bound as *mut u8, Layout::new::<BoundAdapter>());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::debug::{sffi_debug_live_count, sffi_debug_set_leak_tracking, SFFI_DEBUG_ADAPTER, SFFI_DEBUG_BOUND_ADAPTER};
+    use crate::api::error::{sffi_error_code, sffi_error_free, SFFI_INVALID_ARGUMENTS_ERR};
+    use crate::api::library::{sffi_lib_close, sffi_lib_func, sffi_lib_open};
+    use crate::api::TEST_LOCK;
+    use crate::library::LibHandle;
+    use std::ptr::null;
+
+    #[test]
+    fn test_bound_adapter() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let mut lib = null_mut::<LibHandle>();
+            assert!(sffi_lib_open(&mut lib, c"".as_ptr()).is_null());
+            let mut func = null::<FuncHandle>();
+            assert!(sffi_lib_func(&mut func, lib, c"labs".as_ptr(), c"(i64)i64".as_ptr()).is_null());
+            sffi_debug_set_leak_tracking(true);
+            let mut adapter = null_mut::<Adapter>();
+            assert!(sffi_adapter_parse(&mut adapter, c"(i8)i32".as_ptr()).is_null());
+            let mut bound = null_mut::<BoundAdapter>();
+            assert!(sffi_adapter_bind(&mut bound, adapter, func).is_null());
+            sffi_adapter_free(adapter);
+            assert_eq!((sffi_debug_live_count(SFFI_DEBUG_ADAPTER), sffi_debug_live_count(SFFI_DEBUG_BOUND_ADAPTER)), (0, 1));
+
+            let (mut value, mut result) = (-7i8, 0i32);
+            let mut arguments = [&mut value as *mut i8 as *mut c_void];
+            assert!(sffi_bound_adapter_call(bound, &mut result as *mut i32 as *mut c_void, 1, arguments.as_mut_ptr()).is_null());
+            assert_eq!(result, 7);
+
+            let error = sffi_bound_adapter_call(bound, &mut result as *mut i32 as *mut c_void, 0, arguments.as_mut_ptr());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+
+            assert!(sffi_bound_adapter_free_checked(bound).is_null());
+            assert_eq!(sffi_debug_live_count(SFFI_DEBUG_BOUND_ADAPTER), 0);
+            sffi_debug_set_leak_tracking(false);
+            sffi_lib_close(lib);
+        }
+    }
+}
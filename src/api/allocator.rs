@@ -0,0 +1,68 @@
+use crate::allocator::{set_allocator, Allocator};
+use crate::api::error::{guard, CError};
+use crate::error::Error;
+use std::ffi::c_void;
+use std::ptr::null_mut;
+
+pub type AllocFn = unsafe extern "C" fn(size: usize, align: usize, data: *mut c_void) -> *mut c_void;
+pub type FreeFn = unsafe extern "C" fn(ptr: *mut c_void, data: *mut c_void);
+
+/// # Safety
+///
+/// `alloc` and `free` must stay callable with `data` until the allocator is reset, and blocks from one allocator must never be handed to the other.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_set_allocator(alloc: Option<AllocFn>, free: Option<FreeFn>, data: *mut c_void) -> *mut CError {
+    guard("sffi_set_allocator", || {
+        match (alloc, free) {
+            (Some(alloc), Some(free)) => set_allocator(Some(Allocator { alloc, free, data })),
+            (None, None) => set_allocator(None),
+            _ => return Error::InvalidArguments { index: None, message: Some("Both alloc and free must be set or null".to_string()) }.into(),
+        }
+        null_mut()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocator::tests::{counting_alloc, counting_free, Installed, COUNT, FAIL};
+    use crate::api::error::{sffi_error_code, sffi_error_free, SFFI_ALLOC_ERR, SFFI_INVALID_ARGUMENTS_ERR, SFFI_NO_ERR};
+    use crate::api::structure::{sffi_struct_free, sffi_struct_malloc_checked, sffi_struct_type_alloc, sffi_struct_type_free};
+    use crate::structure::StructType;
+
+    #[test]
+    fn test_set_allocator() {
+        let _installed = Installed::new(None);
+        unsafe {
+            let error = sffi_set_allocator(Some(counting_alloc), None, null_mut());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+
+            assert_eq!(sffi_error_code(sffi_set_allocator(Some(counting_alloc), Some(counting_free), null_mut())), SFFI_NO_ERR);
+            let mut struct_type = null_mut::<StructType>();
+            assert!(sffi_struct_type_alloc(&mut struct_type, c"[i32,i64]".as_ptr()).is_null());
+            let before = COUNT.with(|it| it.get());
+            let mut structure = null_mut();
+            assert!(sffi_struct_malloc_checked(&mut structure, struct_type).is_null());
+            assert_eq!(COUNT.with(|it| it.get()), before + 1);
+            sffi_struct_free(structure);
+            assert_eq!(COUNT.with(|it| it.get()), before);
+
+            // A struct outliving its allocator is still freed by it
+            assert!(sffi_struct_malloc_checked(&mut structure, struct_type).is_null());
+            assert_eq!(sffi_error_code(sffi_set_allocator(None, None, null_mut())), SFFI_NO_ERR);
+            sffi_struct_free(structure);
+            assert_eq!(COUNT.with(|it| it.get()), before);
+            assert_eq!(sffi_error_code(sffi_set_allocator(Some(counting_alloc), Some(counting_free), null_mut())), SFFI_NO_ERR);
+
+            FAIL.with(|it| it.set(true));
+            let error = sffi_struct_malloc_checked(&mut structure, struct_type);
+            FAIL.with(|it| it.set(false));
+            assert_eq!(sffi_error_code(error), SFFI_ALLOC_ERR);
+            sffi_error_free(error);
+
+            sffi_struct_type_free(struct_type);
+            assert_eq!(sffi_error_code(sffi_set_allocator(None, None, null_mut())), SFFI_NO_ERR);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::api::error::guard_or;
+use crate::tracking::{live_count, report_leaks, set_leak_tracking, Resource};
+use std::ffi::c_uint;
+
+pub const SFFI_DEBUG_LIBRARY       : c_uint = 0;
+pub const SFFI_DEBUG_ADAPTER       : c_uint = 1;
+pub const SFFI_DEBUG_STRUCT_TYPE   : c_uint = 2;
+pub const SFFI_DEBUG_ERROR         : c_uint = 3;
+pub const SFFI_DEBUG_STRUCT        : c_uint = 4;
+pub const SFFI_DEBUG_STRING        : c_uint = 5;
+pub const SFFI_DEBUG_BOUND_ADAPTER : c_uint = 6;
+
+const RESOURCES: [Resource; 7] = [Resource::Library, Resource::Adapter, Resource::StructType, Resource::Error, Resource::Struct, Resource::String, Resource::BoundAdapter];
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_debug_set_leak_tracking(enabled: bool) {
+    guard_or("sffi_debug_set_leak_tracking", (), || set_leak_tracking(enabled))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_debug_live_count(resource: c_uint) -> c_uint {
+    guard_or("sffi_debug_live_count", 0, || {
+        match RESOURCES.get(resource as usize) {
+            Some(resource) => live_count(*resource) as c_uint,
+            None => 0,
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_debug_report_leaks() -> c_uint {
+    guard_or("sffi_debug_report_leaks", 0, || report_leaks(&RESOURCES) as c_uint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Adapter;
+    use crate::api::adapter::{sffi_adapter_free, sffi_adapter_get, sffi_adapter_parse, sffi_string_free};
+    use crate::api::error::sffi_error_free;
+    use crate::api::structure::{sffi_struct_calloc, sffi_struct_free, sffi_struct_type_alloc, sffi_struct_type_free};
+    use crate::api::TEST_LOCK;
+    use crate::structure::StructType;
+    use crate::tracking::lock;
+    use std::ffi::{c_char, c_void};
+    use std::ptr::null_mut;
+
+    #[test]
+    fn test_leaks() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            sffi_debug_set_leak_tracking(true);
+            let mut struct_type = null_mut::<StructType>();
+            assert!(sffi_struct_type_alloc(&mut struct_type, c"[enum Color{Red,Green}]".as_ptr()).is_null());
+            let structure = sffi_struct_calloc(struct_type);
+            let mut adapter = null_mut::<Adapter>();
+            assert!(sffi_adapter_parse(&mut adapter, c"[*str]".as_ptr()).is_null());
+            let mut name = null_mut::<c_char>();
+            assert!(sffi_adapter_get(adapter, struct_type, structure, 0, &mut name as *mut *mut c_char as *mut c_void).is_null());
+            let error = sffi_adapter_parse(&mut adapter, c"[q32]".as_ptr());
+
+            assert_eq!(sffi_debug_live_count(SFFI_DEBUG_STRING), 1);
+            assert_eq!(sffi_debug_live_count(SFFI_DEBUG_ERROR), 1);
+            assert_eq!(sffi_debug_report_leaks(), 5);
+            assert!(lock().as_ref().unwrap().values().any(|it| it.resource == Resource::String && it.site == "sffi_adapter_get"));
+
+            sffi_string_free(name);
+            sffi_error_free(error);
+            sffi_adapter_free(adapter);
+            sffi_struct_free(structure);
+            sffi_struct_type_free(struct_type);
+            assert_eq!(sffi_debug_report_leaks(), 0);
+            sffi_debug_set_leak_tracking(false);
+        }
+    }
+}
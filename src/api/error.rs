@@ -1,12 +1,17 @@
+use crate::tracking::{enter, track, untrack, Resource};
 use crate::error::Error;
 use crate::internal::try_str_to_c_string;
 use std::alloc::{alloc, dealloc, Layout};
-use std::ffi::{c_char, c_uint, CString};
+use std::ffi::{c_char, c_uint, c_void, CString};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::cell::RefCell;
 use std::ptr::{drop_in_place, null, null_mut};
 
 pub struct CError {
     code: c_uint,
     message: *mut c_char,
+    report: *mut c_char,
 }
 
 pub const SFFI_NO_ERR                   : c_uint = 0;
@@ -21,55 +26,217 @@ pub const SFFI_FFI_BAD_ARG_TYPEE_ERR    : c_uint = 8;
 pub const SFFI_INVALID_DESCRIPTOR_ERR   : c_uint = 9;
 pub const SFFI_INVALID_CAST_ERR         : c_uint = 10;
 pub const SFFI_INVALID_ARGUMENTS_ERR    : c_uint = 11;
+pub const SFFI_LIB_NOT_FOUND_ERR        : c_uint = 12;
+pub const SFFI_LIB_WRONG_ARCH_ERR       : c_uint = 13;
+pub const SFFI_LIB_MISSING_DEP_ERR      : c_uint = 14;
+pub const SFFI_LIB_UNDEFINED_SYMBOL_ERR : c_uint = 15;
+pub const SFFI_LIB_PERMISSION_ERR       : c_uint = 16;
+pub const SFFI_PANIC_ERR                : c_uint = 17;
+pub const SFFI_INVALID_HANDLE_ERR       : c_uint = 18;
+pub const SFFI_ALLOC_ERR                : c_uint = 19;
+
+thread_local! {
+    static POISON: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_error_code(error: *const CError) -> c_uint {
-    unsafe {
+    guard_or("sffi_error_code", SFFI_PANIC_ERR, || unsafe {
         if error.is_null() { return SFFI_NO_ERR }
         (*error).code
-    }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_error_msg(error: *const CError) -> *const c_char {
-    unsafe {
+    guard_or("sffi_error_msg", null(), || unsafe {
         if error.is_null() { return null() }
         (*error).message
-    }
+    })
+}
+
+/// # Safety
+///
+/// `error` must be NULL or an error that has not been freed. The returned string lives as long as `error`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_error_report(error: *const CError) -> *const c_char {
+    guard_or("sffi_error_report", null(), || unsafe {
+        if error.is_null() { return null() }
+        (*error).report
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_error_free(error: *mut CError) {
-    unsafe {
+    guard_or("sffi_error_free", (), || unsafe {
         if error.is_null() { return }
+        untrack(error as *const c_void);
         if !(*error).message.is_null() { let _ = CString::from_raw((*error).message); }
+        if !(*error).report.is_null() { let _ = CString::from_raw((*error).report); }
         drop_in_place(error);
         dealloc(error as *mut u8, Layout::new::<CError>());
-    }
+    })
 }
 
 impl From<Error> for *mut CError {
     fn from(value: Error) -> Self {
         unsafe {
-            let code = match value {
-                Error::RustStringToCString => SFFI_RUST_STR_TO_C_STR_ERR,
+            let code = match value.root() {
+                Error::RustStringToCString(_) => SFFI_RUST_STR_TO_C_STR_ERR,
                 Error::CStringToRustString => SFFI_C_STR_TO_RUST_STR_ERR,
-                Error::LibraryOpen(_) => SFFI_LIB_OPEN_ERR,
-                Error::LibrarySymbol(_) => SFFI_LIB_SYMBOL_ERR,
-                Error::LibraryClose(_) => SFFI_LIB_CLOSE_ERR,
+                Error::LibraryOpen { .. } => SFFI_LIB_OPEN_ERR,
+                Error::LibraryNotFound { .. } => SFFI_LIB_NOT_FOUND_ERR,
+                Error::LibraryWrongArch { .. } => SFFI_LIB_WRONG_ARCH_ERR,
+                Error::LibraryMissingDependency { .. } => SFFI_LIB_MISSING_DEP_ERR,
+                Error::LibraryUndefinedSymbol { .. } => SFFI_LIB_UNDEFINED_SYMBOL_ERR,
+                Error::LibraryPermissionDenied { .. } => SFFI_LIB_PERMISSION_ERR,
+                Error::LibrarySymbol { .. } => SFFI_LIB_SYMBOL_ERR,
+                Error::LibraryClose { .. } => SFFI_LIB_CLOSE_ERR,
                 Error::FFIBadTypeDef => SFFI_FFI_BAD_TYPEDEF_ERR,
                 Error::FFIBadABI => SFFI_FFI_BAD_ABI_ERR,
                 Error::FFIBadArgType => SFFI_FFI_BAD_ARG_TYPEE_ERR,
-                Error::InvalidDescriptor(_) => SFFI_INVALID_DESCRIPTOR_ERR,
-                Error::InvalidCast(_) => SFFI_INVALID_CAST_ERR,
-                Error::InvalidArguments(_) => SFFI_INVALID_ARGUMENTS_ERR,
+                Error::InvalidDescriptor { .. } => SFFI_INVALID_DESCRIPTOR_ERR,
+                Error::InvalidCast { .. } => SFFI_INVALID_CAST_ERR,
+                Error::InvalidArguments { .. } => SFFI_INVALID_ARGUMENTS_ERR,
+                Error::Panic { .. } => SFFI_PANIC_ERR,
+                Error::InvalidHandle { .. } => SFFI_INVALID_HANDLE_ERR,
+                Error::Allocation { .. } => SFFI_ALLOC_ERR,
+                Error::Function { .. } | Error::Argument { .. } => unreachable!(),
             };
-            let message = value.get_message();
+            let message = value.root().get_message();
             let message = if let Some(message) = message && let Ok(message) = try_str_to_c_string(message) { message.into_raw() } else { null_mut() };
+            let report = if let Ok(report) = try_str_to_c_string(&value.report()) { report.into_raw() } else { null_mut() };
             let error = alloc(Layout::new::<CError>()) as *mut CError;
             (*error).code = code;
             (*error).message = message;
+            (*error).report = report;
+            track(Resource::Error, error as *const c_void);
             error
         }
     }
-}
\ No newline at end of file
+}
+
+pub(crate) fn null_argument(name: &str) -> *mut CError {
+    Error::InvalidArguments { index: None, message: Some(format!("Argument '{}' is null", name)) }.into()
+}
+
+pub(crate) fn guard(site: &'static str, func: impl FnOnce() -> *mut CError) -> *mut CError {
+    let _site = enter(site);
+    match catch_unwind(AssertUnwindSafe(func)) {
+        Ok(error) => error,
+        Err(payload) => Error::Panic { message: panic_message(payload) }.into(),
+    }
+}
+
+pub(crate) fn guard_or<T>(site: &'static str, default: T, func: impl FnOnce() -> T) -> T {
+    let _site = enter(site);
+    match catch_unwind(AssertUnwindSafe(func)) {
+        Ok(value) => value,
+        Err(payload) => {
+            let message = panic_message(payload).and_then(|it| try_str_to_c_string(&it).ok());
+            POISON.with(|it| *it.borrow_mut() = Some(message.unwrap_or_default()));
+            default
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> Option<String> {
+    match payload.downcast::<String>() {
+        Ok(message) => Some(*message),
+        Err(payload) => payload.downcast_ref::<&str>().map(|it| it.to_string()),
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_poisoned() -> bool {
+    POISON.with(|it| it.borrow().is_some())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_poison_msg() -> *const c_char {
+    POISON.with(|it| it.borrow().as_ref().map_or(null(), |it| it.as_ptr()))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_poison_clear() {
+    POISON.with(|it| *it.borrow_mut() = None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::adapter::{sffi_adapter_call, sffi_adapter_free};
+    use crate::api::library::{sffi_lib_close, sffi_lib_func, sffi_lib_open, sffi_lib_symbol};
+    use crate::api::structure::{sffi_struct_type_size, sffi_struct_type_size_checked};
+    use crate::api::TEST_LOCK;
+    use crate::interface::FuncHandle;
+    use crate::library::LibHandle;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_panic() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let error = guard("test_panic", || panic!("failed at {}", 3));
+            assert_eq!(sffi_error_code(error), SFFI_PANIC_ERR);
+            assert_eq!(CStr::from_ptr(sffi_error_msg(error)), c"failed at 3");
+            assert_eq!(CStr::from_ptr(sffi_error_report(error)), c"Panic: failed at 3");
+            sffi_error_free(error);
+
+            let error = guard("test_panic", || std::panic::panic_any(7));
+            assert_eq!(sffi_error_code(error), SFFI_PANIC_ERR);
+            sffi_error_free(error);
+            assert!(!sffi_poisoned());
+        }
+    }
+
+    #[test]
+    fn test_null() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let mut handle = null_mut::<LibHandle>();
+            let error = sffi_lib_open(&mut handle, null());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            assert_eq!(CStr::from_ptr(sffi_error_msg(error)), c"Argument 'name' is null");
+            sffi_error_free(error);
+            assert!(handle.is_null());
+            assert!(sffi_lib_open(&mut handle, c"".as_ptr()).is_null());
+            let error = sffi_lib_symbol(null_mut(), handle, c"puts".as_ptr());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            assert_eq!(CStr::from_ptr(sffi_error_msg(error)), c"Argument 'r_symbol' is null");
+            assert_eq!(CStr::from_ptr(sffi_error_report(error)), c"Invalid arguments: Argument 'r_symbol' is null");
+            sffi_error_free(error);
+            let mut func = null::<FuncHandle>();
+            let error = sffi_lib_func(&mut func, handle, c"puts".as_ptr(), null());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+            sffi_lib_close(handle);
+
+            let mut size = 0;
+            let error = sffi_struct_type_size_checked(&mut size, null());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+            assert_eq!(sffi_struct_type_size(null()), 0);
+            let error = sffi_adapter_call(null(), null(), null_mut(), 0, null_mut());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+            sffi_adapter_free(null_mut());
+        }
+    }
+
+    #[test]
+    fn test_poison() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        assert_eq!(guard_or("test_poison", 7, || panic!("poisoned by {}", "test")), 7);
+        assert!(sffi_poisoned());
+        assert_eq!(unsafe { CStr::from_ptr(sffi_poison_msg()) }, c"poisoned by test");
+        std::thread::spawn(|| {
+            assert!(!sffi_poisoned());
+            guard_or("test_poison", (), || panic!("other thread"));
+            sffi_poison_clear();
+        }).join().unwrap();
+        assert_eq!(unsafe { CStr::from_ptr(sffi_poison_msg()) }, c"poisoned by test");
+        sffi_poison_clear();
+        assert!(!sffi_poisoned());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::api::error::{guard, guard_or, CError};
+use crate::error::Error;
+use std::ffi::c_void;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Library, Adapter, BoundAdapter, StructType, Func
+}
+
+struct Slot {
+    generation: usize,
+    kind: Kind,
+    value: *mut c_void,
+    parent: Option<usize>,
+    detached: bool,
+}
+
+struct Table {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+}
+
+// The table only stores the pointers, the objects behind them are owned by the C host.
+unsafe impl Send for Table {}
+
+static CHECKING: AtomicBool = AtomicBool::new(cfg!(feature = "handle-checking"));
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static TABLE: Mutex<Table> = Mutex::new(Table { slots: Vec::new(), free: Vec::new() });
+
+const INDEX_BITS: u32 = usize::BITS / 2;
+const INDEX_MASK: usize = (1 << INDEX_BITS) - 1;
+const GENERATION_MASK: usize = usize::MAX >> INDEX_BITS;
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Library => write!(f, "library"),
+            Kind::Adapter => write!(f, "adapter"),
+            Kind::BoundAdapter => write!(f, "bound adapter"),
+            Kind::StructType => write!(f, "struct type"),
+            Kind::Func => write!(f, "function"),
+        }
+    }
+}
+
+impl Kind {
+    fn freed_with_parent(self) -> bool {
+        matches!(self, Kind::Func)
+    }
+}
+
+fn lock() -> MutexGuard<'static, Table> {
+    TABLE.lock().unwrap_or_else(|it| it.into_inner())
+}
+
+pub(crate) fn register<T>(kind: Kind, value: *mut T) -> *mut T {
+    LIVE.fetch_add(1, Ordering::SeqCst);
+    if !CHECKING.load(Ordering::SeqCst) { return value }
+    let mut table = lock();
+    let index = occupy(&mut table, kind, value as *mut c_void, None);
+    handle_of(&table, index)
+}
+
+pub(crate) fn register_in<T, P>(kind: Kind, value: *mut T, parent_kind: Kind, parent: *const P) -> Result<*mut T, Error> {
+    if !CHECKING.load(Ordering::SeqCst) {
+        if !kind.freed_with_parent() { LIVE.fetch_add(1, Ordering::SeqCst); }
+        return Ok(value)
+    }
+    let mut table = lock();
+    let parent = find(&table, parent_kind, parent)?;
+    if !kind.freed_with_parent() { LIVE.fetch_add(1, Ordering::SeqCst); }
+    let owned = table.slots.iter().position(|it| it.kind == kind && it.value == value as *mut c_void && it.parent == Some(parent));
+    let index = match owned {
+        Some(index) => index,
+        None => occupy(&mut table, kind, value as *mut c_void, Some(parent)),
+    };
+    Ok(handle_of(&table, index))
+}
+
+fn occupy(table: &mut Table, kind: Kind, value: *mut c_void, parent: Option<usize>) -> usize {
+    match table.free.pop() {
+        Some(index) => {
+            let slot = &mut table.slots[index];
+            slot.kind = kind;
+            slot.value = value;
+            slot.parent = parent;
+            slot.detached = false;
+            index
+        }
+        None => {
+            table.slots.push(Slot { generation: 1, kind, value, parent, detached: false });
+            table.slots.len() - 1
+        }
+    }
+}
+
+fn handle_of<T>(table: &Table, index: usize) -> *mut T {
+    ((table.slots[index].generation << INDEX_BITS) | (index + 1)) as *mut T
+}
+
+pub(crate) fn resolve<T>(kind: Kind, handle: *const T) -> Result<*mut T, Error> {
+    if !CHECKING.load(Ordering::SeqCst) { return Ok(handle as *mut T) }
+    let table = lock();
+    let index = find(&table, kind, handle)?;
+    Ok(table.slots[index].value as *mut T)
+}
+
+pub(crate) fn release<T>(kind: Kind, handle: *mut T) -> Result<*mut T, Error> {
+    if !CHECKING.load(Ordering::SeqCst) {
+        LIVE.fetch_sub(1, Ordering::SeqCst);
+        return Ok(handle)
+    }
+    let mut table = lock();
+    let index = locate(&table, kind, handle)?;
+    let value = table.slots[index].value as *mut T;
+    vacate(&mut table, index);
+    LIVE.fetch_sub(1, Ordering::SeqCst);
+    Ok(value)
+}
+
+fn vacate(table: &mut Table, index: usize) {
+    let slot = &mut table.slots[index];
+    slot.value = null_mut();
+    slot.parent = None;
+    slot.detached = false;
+    slot.generation = match (slot.generation + 1) & GENERATION_MASK { 0 => 1, generation => generation };
+    table.free.push(index);
+
+    // Children freed with their parent go away with it, the others can't be used anymore but are still freed by the C host
+    let children = (0..table.slots.len()).filter(|it| table.slots[*it].parent == Some(index)).collect::<Vec<_>>();
+    for child in children {
+        if table.slots[child].kind.freed_with_parent() {
+            vacate(table, child)
+        } else {
+            table.slots[child].parent = None;
+            table.slots[child].detached = true;
+        }
+    }
+}
+
+fn find<T>(table: &Table, kind: Kind, handle: *const T) -> Result<usize, Error> {
+    let index = locate(table, kind, handle)?;
+    if table.slots[index].detached { return invalid_handle(format!("The {} of handle {:#x} was freed with its owner", kind, handle as usize)) }
+    Ok(index)
+}
+
+fn locate<T>(table: &Table, kind: Kind, handle: *const T) -> Result<usize, Error> {
+    let handle = handle as usize;
+    let (index, generation) = ((handle & INDEX_MASK).wrapping_sub(1), handle >> INDEX_BITS);
+    let slot = if let Some(slot) = table.slots.get(index) { slot } else { return invalid_handle(format!("Unknown {} handle {:#x}", kind, handle)) };
+    if slot.generation != generation || slot.value.is_null() { return invalid_handle(format!("Stale {} handle {:#x}, it was already freed", kind, handle)) }
+    if slot.kind != kind { return invalid_handle(format!("Handle {:#x} of {} used as {}", handle, slot.kind, kind)) }
+    Ok(index)
+}
+
+fn invalid_handle<T>(message: String) -> Result<T, Error> {
+    Err(Error::InvalidHandle { message: Some(message) })
+}
+
+pub(crate) fn set_checking(enabled: bool) -> Result<(), Error> {
+    let _table = lock();
+    let live = LIVE.load(Ordering::SeqCst);
+    if CHECKING.load(Ordering::SeqCst) != enabled && live > 0 {
+        return Error::invalid_args_from_string(format!("Handle checking can't be switched with {} live handles", live))
+    }
+    CHECKING.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_handle_checking() -> bool {
+    guard_or("sffi_handle_checking", false, || CHECKING.load(Ordering::SeqCst))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_set_handle_checking(enabled: bool) -> *mut CError {
+    guard("sffi_set_handle_checking", || {
+        match set_checking(enabled) {
+            Ok(_) => null_mut(),
+            Err(error) => error.into()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Adapter;
+    use crate::api::adapter::{sffi_adapter_bind, sffi_adapter_free, sffi_adapter_parse, sffi_adapter_set, sffi_bound_adapter_call, sffi_bound_adapter_free_checked};
+    use crate::api::interface::{sffi_func_arg_count, sffi_func_as_raw};
+    use crate::api::library::{sffi_lib_close, sffi_lib_func, sffi_lib_open};
+    use crate::bound::BoundAdapter;
+    use crate::interface::FuncHandle;
+    use crate::library::LibHandle;
+    use std::ptr::null;
+    use crate::api::error::{sffi_error_code, sffi_error_free, SFFI_INVALID_ARGUMENTS_ERR, SFFI_INVALID_HANDLE_ERR, SFFI_NO_ERR};
+    use crate::api::structure::{sffi_struct_type_alloc, sffi_struct_type_free_checked, sffi_struct_type_size};
+    use crate::api::TEST_LOCK;
+    use crate::structure::StructType;
+
+    #[test]
+    fn test_checking() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            assert_eq!(sffi_error_code(sffi_set_handle_checking(true)), SFFI_NO_ERR);
+            let mut struct_type = null_mut::<StructType>();
+            assert!(sffi_struct_type_alloc(&mut struct_type, c"[i32,i64]".as_ptr()).is_null());
+            assert_eq!(sffi_struct_type_size(struct_type), 16);
+
+            let error = sffi_set_handle_checking(false);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+
+            let mut structure = 0u128;
+            let structure = &mut structure as *mut u128 as *mut c_void;
+            let error = sffi_adapter_set(struct_type as *const Adapter, struct_type, structure, 0, &1i32 as *const i32 as *const c_void);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_HANDLE_ERR);
+            sffi_error_free(error);
+
+            assert!(sffi_struct_type_free_checked(struct_type).is_null());
+            assert_eq!(sffi_struct_type_size(struct_type), 0);
+            let error = sffi_struct_type_free_checked(struct_type);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_HANDLE_ERR);
+            sffi_error_free(error);
+
+            assert_eq!(sffi_error_code(sffi_set_handle_checking(false)), SFFI_NO_ERR);
+        }
+    }
+
+    #[test]
+    fn test_func() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            assert_eq!(sffi_error_code(sffi_set_handle_checking(true)), SFFI_NO_ERR);
+            let mut lib = null_mut::<LibHandle>();
+            assert!(sffi_lib_open(&mut lib, c"".as_ptr()).is_null());
+            let (mut func, mut same) = (null::<FuncHandle>(), null::<FuncHandle>());
+            assert!(sffi_lib_func(&mut func, lib, c"abs".as_ptr(), c"(int)int".as_ptr()).is_null());
+            assert!(sffi_lib_func(&mut same, lib, c"abs".as_ptr(), c"(int)int".as_ptr()).is_null());
+            assert_eq!(func, same);
+            assert!(!sffi_func_as_raw(func).is_null());
+            assert!(sffi_func_as_raw(lib as *const FuncHandle).is_null());
+
+            // Functions are freed with their library, so are their handles
+            sffi_lib_close(lib);
+            let mut count = 0;
+            let error = sffi_func_arg_count(&mut count, func);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_HANDLE_ERR);
+            sffi_error_free(error);
+            let mut adapter = null_mut::<Adapter>();
+            assert!(sffi_adapter_parse(&mut adapter, c"(i32)i32".as_ptr()).is_null());
+            let mut bound = null_mut::<BoundAdapter>();
+            let error = sffi_adapter_bind(&mut bound, adapter, func);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_HANDLE_ERR);
+            sffi_error_free(error);
+            sffi_adapter_free(adapter);
+
+            assert_eq!(sffi_error_code(sffi_set_handle_checking(false)), SFFI_NO_ERR);
+        }
+    }
+
+    #[test]
+    fn test_bound_after_close() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            assert_eq!(sffi_error_code(sffi_set_handle_checking(true)), SFFI_NO_ERR);
+            let mut lib = null_mut::<LibHandle>();
+            assert!(sffi_lib_open(&mut lib, c"".as_ptr()).is_null());
+            let mut func = null::<FuncHandle>();
+            assert!(sffi_lib_func(&mut func, lib, c"abs".as_ptr(), c"(int)int".as_ptr()).is_null());
+            let mut adapter = null_mut::<Adapter>();
+            assert!(sffi_adapter_parse(&mut adapter, c"(i32)i32".as_ptr()).is_null());
+            let mut bound = null_mut::<BoundAdapter>();
+            assert!(sffi_adapter_bind(&mut bound, adapter, func).is_null());
+            sffi_adapter_free(adapter);
+
+            let (mut value, mut result) = (-3i32, 0i32);
+            let mut arguments = [&mut value as *mut i32 as *mut c_void];
+            assert!(sffi_bound_adapter_call(bound, &mut result as *mut i32 as *mut c_void, 1, arguments.as_mut_ptr()).is_null());
+            assert_eq!(result, 3);
+
+            sffi_lib_close(lib);
+            let error = sffi_bound_adapter_call(bound, &mut result as *mut i32 as *mut c_void, 1, arguments.as_mut_ptr());
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_HANDLE_ERR);
+            sffi_error_free(error);
+
+            // The bound adapter is still freed by the C host
+            assert!(sffi_bound_adapter_free_checked(bound).is_null());
+            let error = sffi_bound_adapter_free_checked(bound);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_HANDLE_ERR);
+            sffi_error_free(error);
+
+            assert_eq!(sffi_error_code(sffi_set_handle_checking(false)), SFFI_NO_ERR);
+        }
+    }
+}
@@ -1,18 +1,282 @@
-use crate::interface::FuncHandle;
+use crate::api::error::{guard, guard_or, CError};
+use crate::api::handle::{resolve, Kind};
+use crate::api::structure::copy_struct_type;
+use crate::error::Error;
+use crate::interface::{FuncHandle, FuncType};
+use crate::internal::{strdup_c_const_char, try_str_to_c_string};
+use crate::structure::StructType;
 use libffi::raw::ffi_raw;
-use std::ffi::c_void;
+use std::ffi::{c_char, c_uint, c_void};
+use std::ptr::{null, null_mut};
+
+pub const SFFI_TYPE_AUTO                : c_uint = 0;
+pub const SFFI_TYPE_VOID                : c_uint = 1;
+pub const SFFI_TYPE_INT                 : c_uint = 2;
+pub const SFFI_TYPE_FLOAT               : c_uint = 3;
+pub const SFFI_TYPE_DOUBLE              : c_uint = 4;
+pub const SFFI_TYPE_LONG_DOUBLE         : c_uint = 5;
+pub const SFFI_TYPE_ISIZE               : c_uint = 6;
+pub const SFFI_TYPE_USIZE               : c_uint = 7;
+pub const SFFI_TYPE_I8                  : c_uint = 8;
+pub const SFFI_TYPE_I16                 : c_uint = 9;
+pub const SFFI_TYPE_I32                 : c_uint = 10;
+pub const SFFI_TYPE_I64                 : c_uint = 11;
+pub const SFFI_TYPE_I128                : c_uint = 12;
+pub const SFFI_TYPE_U8                  : c_uint = 13;
+pub const SFFI_TYPE_U16                 : c_uint = 14;
+pub const SFFI_TYPE_U32                 : c_uint = 15;
+pub const SFFI_TYPE_U64                 : c_uint = 16;
+pub const SFFI_TYPE_U128                : c_uint = 17;
+pub const SFFI_TYPE_F32                 : c_uint = 18;
+pub const SFFI_TYPE_F64                 : c_uint = 19;
+pub const SFFI_TYPE_F128                : c_uint = 20;
+pub const SFFI_TYPE_COMPLEX_F32         : c_uint = 21;
+pub const SFFI_TYPE_COMPLEX_F64         : c_uint = 22;
+pub const SFFI_TYPE_COMPLEX_LONG_DOUBLE : c_uint = 23;
+pub const SFFI_TYPE_POINTER             : c_uint = 24;
+pub const SFFI_TYPE_REF_STR             : c_uint = 25;
+pub const SFFI_TYPE_BORROW_STR          : c_uint = 26;
+pub const SFFI_TYPE_REF_ARRAY           : c_uint = 27;
+pub const SFFI_TYPE_BORROW_ARRAY        : c_uint = 28;
+pub const SFFI_TYPE_STRUCT              : c_uint = 29;
+pub const SFFI_TYPE_ENUM                : c_uint = 30;
+pub const SFFI_TYPE_FLAGS               : c_uint = 31;
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_func_call(func: *const FuncHandle, rvalue: *mut c_void, avalue: *mut *mut c_void) {
-    unsafe { (*func)._call(rvalue, avalue); }
+    guard_or("sffi_func_call", (), || unsafe {
+        if func.is_null() { return }
+        let Ok(func) = resolve(Kind::Func, func) else { return };
+        (*func)._call(rvalue, avalue);
+    })
+}
+
+/// # Safety
+///
+/// `func` must be a live function handle, `rvalue` must have room for the return value and `avalue` must point to one value pointer per argument.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_call_checked(func: *const FuncHandle, rvalue: *mut c_void, avalue: *mut *mut c_void) -> *mut CError {
+    guard("sffi_func_call_checked", || unsafe {
+        check_null!(func);
+        let func = resolve!(Func, func);
+        if !(*func).desc().return_type().is_void() { check_null!(rvalue); }
+        if !(*func).desc().argument_types().is_empty() { check_null!(avalue); }
+        (*func)._call(rvalue, avalue);
+        null_mut()
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_func_call_raw(func: *const FuncHandle, rvalue: *mut c_void, avalue: *mut ffi_raw) {
-    unsafe { (*func)._call_raw(rvalue, avalue) }
+    guard_or("sffi_func_call_raw", (), || unsafe {
+        if func.is_null() { return }
+        let Ok(func) = resolve(Kind::Func, func) else { return };
+        (*func)._call_raw(rvalue, avalue)
+    })
+}
+
+/// # Safety
+///
+/// `func` must be a live function handle and `avalue` must be laid out as libffi's raw argument array for its signature.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_call_raw_checked(func: *const FuncHandle, rvalue: *mut c_void, avalue: *mut ffi_raw) -> *mut CError {
+    guard("sffi_func_call_raw_checked", || unsafe {
+        check_null!(func);
+        let func = resolve!(Func, func);
+        if !(*func).desc().return_type().is_void() { check_null!(rvalue); }
+        if !(*func).desc().argument_types().is_empty() { check_null!(avalue); }
+        (*func)._call_raw(rvalue, avalue);
+        null_mut()
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_func_as_raw(func: *const FuncHandle) -> *const c_void {
-    unsafe { (*func).as_raw() }
-}
\ No newline at end of file
+    guard_or("sffi_func_as_raw", null(), || unsafe {
+        if func.is_null() { return null() }
+        let Ok(func) = resolve(Kind::Func, func) else { return null() };
+        (*func).as_raw()
+    })
+}
+
+/// # Safety
+///
+/// `r_raw` must be writable and `func` must be a live function handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_as_raw_checked(r_raw: *mut *const c_void, func: *const FuncHandle) -> *mut CError {
+    guard("sffi_func_as_raw_checked", || unsafe {
+        check_null!(r_raw, func);
+        let func = resolve!(Func, func);
+        *r_raw = (*func).as_raw();
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `r_count` must be writable and `func` must be a live function handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_arg_count(r_count: *mut c_uint, func: *const FuncHandle) -> *mut CError {
+    guard("sffi_func_arg_count", || unsafe {
+        check_null!(r_count, func);
+        let func = resolve!(Func, func);
+        *r_count = (*func).desc().argument_types().len() as c_uint;
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// The `r_*` outputs must be writable and `func` must be a live function handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_arg_type(r_kind: *mut c_uint, r_size: *mut c_uint, r_align: *mut c_uint, func: *const FuncHandle, index: c_uint) -> *mut CError {
+    guard("sffi_func_arg_type", || unsafe {
+        check_null!(r_kind, r_size, r_align, func);
+        let func = resolve!(Func, func);
+        let argument_type = match argument(&*func, index) { Ok(argument_type) => argument_type, Err(error) => return error.into() };
+        write_type(argument_type, r_kind, r_size, r_align);
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// The `r_*` outputs must be writable and `func` must be a live function handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_ret_type(r_kind: *mut c_uint, r_size: *mut c_uint, r_align: *mut c_uint, func: *const FuncHandle) -> *mut CError {
+    guard("sffi_func_ret_type", || unsafe {
+        check_null!(r_kind, r_size, r_align, func);
+        let func = resolve!(Func, func);
+        write_type((*func).desc().return_type(), r_kind, r_size, r_align);
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `r_struct_type` must be writable and `func` must be a live function handle. The returned struct type is owned by the caller.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_arg_struct(r_struct_type: *mut *mut StructType, func: *const FuncHandle, index: c_uint) -> *mut CError {
+    guard("sffi_func_arg_struct", || unsafe {
+        check_null!(r_struct_type, func);
+        let func = resolve!(Func, func);
+        let argument_type = match argument(&*func, index) { Ok(argument_type) => argument_type, Err(error) => return error.into() };
+        match copy_struct(argument_type) {
+            Ok(struct_type) => { *r_struct_type = struct_type; null_mut() },
+            Err(error) => error.into()
+        }
+    })
+}
+
+/// # Safety
+///
+/// `r_struct_type` must be writable and `func` must be a live function handle. The returned struct type is owned by the caller.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_ret_struct(r_struct_type: *mut *mut StructType, func: *const FuncHandle) -> *mut CError {
+    guard("sffi_func_ret_struct", || unsafe {
+        check_null!(r_struct_type, func);
+        let func = resolve!(Func, func);
+        match copy_struct((*func).desc().return_type()) {
+            Ok(struct_type) => { *r_struct_type = struct_type; null_mut() },
+            Err(error) => error.into()
+        }
+    })
+}
+
+/// # Safety
+///
+/// `r_desc` must be writable and `func` must be a live function handle. The string stored in `r_desc` is freed with `sffi_string_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_func_desc(r_desc: *mut *mut c_char, func: *const FuncHandle) -> *mut CError {
+    guard("sffi_func_desc", || unsafe {
+        check_null!(r_desc, func);
+        let func = resolve!(Func, func);
+        match try_str_to_c_string(&(*func).desc().descriptor()) {
+            Ok(desc) => { *r_desc = strdup_c_const_char(desc.as_ptr()); null_mut() },
+            Err(error) => error.into()
+        }
+    })
+}
+
+fn argument(func: &FuncHandle, index: c_uint) -> Result<&FuncType, Error> {
+    let argument_types = func.desc().argument_types();
+    match argument_types.get(index as usize) {
+        Some(argument_type) => Ok(argument_type),
+        None => Error::invalid_args_from_string(format!("Invalid index ({} / {})", index, argument_types.len()))
+    }
+}
+
+unsafe fn write_type(r#type: &FuncType, r_kind: *mut c_uint, r_size: *mut c_uint, r_align: *mut c_uint) {
+    unsafe {
+        *r_kind = r#type.kind() as c_uint;
+        *r_size = r#type.size();
+        *r_align = r#type.align();
+    }
+}
+
+fn copy_struct(r#type: &FuncType) -> Result<*mut StructType, Error> {
+    match r#type.as_struct() {
+        Some(struct_type) => Ok(copy_struct_type(struct_type)),
+        None => Error::invalid_args_from_string(format!("Type '{}' is not a structure", r#type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::adapter::sffi_string_free;
+    use crate::api::error::{sffi_error_code, sffi_error_free, SFFI_INVALID_ARGUMENTS_ERR};
+    use crate::api::library::{sffi_lib_close, sffi_lib_func, sffi_lib_open};
+    use crate::api::structure::{sffi_struct_type_free, sffi_struct_type_size};
+    use crate::api::TEST_LOCK;
+    use crate::interface::TypeKind;
+    use crate::library::LibHandle;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_kinds() {
+        assert_eq!(TypeKind::Auto as c_uint, SFFI_TYPE_AUTO);
+        assert_eq!(TypeKind::S8 as c_uint, SFFI_TYPE_I8);
+        assert_eq!(TypeKind::ComplexLongDouble as c_uint, SFFI_TYPE_COMPLEX_LONG_DOUBLE);
+        assert_eq!(TypeKind::BorrowArrayPtr as c_uint, SFFI_TYPE_BORROW_ARRAY);
+        assert_eq!(TypeKind::Flags as c_uint, SFFI_TYPE_FLAGS);
+    }
+
+    #[test]
+    fn test_introspection() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let mut lib = null_mut::<LibHandle>();
+            assert!(sffi_lib_open(&mut lib, c"".as_ptr()).is_null());
+            let mut func = null::<FuncHandle>();
+            assert!(sffi_lib_func(&mut func, lib, c"abs".as_ptr(), c"([i8,i64],&str)i32".as_ptr()).is_null());
+
+            let mut count = 0;
+            assert!(sffi_func_arg_count(&mut count, func).is_null());
+            assert_eq!(count, 2);
+
+            let (mut kind, mut size, mut align) = (0, 0, 0);
+            assert!(sffi_func_arg_type(&mut kind, &mut size, &mut align, func, 0).is_null());
+            assert_eq!((kind, size, align), (SFFI_TYPE_STRUCT, 16, 8));
+            assert!(sffi_func_ret_type(&mut kind, &mut size, &mut align, func).is_null());
+            assert_eq!((kind, size, align), (SFFI_TYPE_I32, 4, 4));
+            let error = sffi_func_arg_type(&mut kind, &mut size, &mut align, func, 2);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+
+            let mut struct_type = null_mut();
+            assert!(sffi_func_arg_struct(&mut struct_type, func, 0).is_null());
+            assert_eq!(sffi_struct_type_size(struct_type), 16);
+            sffi_struct_type_free(struct_type);
+            let error = sffi_func_ret_struct(&mut struct_type, func);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+
+            let mut desc = null_mut();
+            assert!(sffi_func_desc(&mut desc, func).is_null());
+            assert_eq!(CStr::from_ptr(desc), c"([i8,i64],&str)i32");
+            sffi_string_free(desc);
+            sffi_lib_close(lib);
+        }
+    }
+}
@@ -1,60 +1,153 @@
-use crate::api::error::CError;
+use crate::api::error::{guard, guard_or, CError};
+use crate::tracking::{track, untrack, Resource};
+use crate::api::handle::{register, register_in, release, resolve, Kind};
 use crate::error::Error;
-use crate::interface::FuncHandle;
+use crate::interface::{FuncHandle, Syntax};
 use crate::internal::try_c_const_char_to_str;
 use crate::library::LibHandle;
 use std::alloc::{alloc, dealloc, Layout};
-use std::ffi::{c_char, c_void};
+use std::ffi::{c_char, c_uint, c_void};
 use std::mem::forget;
 use std::ptr::{drop_in_place, null_mut};
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_lib_open(r_handle: *mut *mut LibHandle, name: *const c_char) -> *mut CError {
-    unsafe {
-        match LibHandle::_open(name) {
-            Ok(handle_) => {
-                let handle = alloc(Layout::new::<LibHandle>()) as *mut LibHandle;
-                handle.copy_from_nonoverlapping(&handle_, 1);
-                forget(handle_);
-                *r_handle = handle;
-                null_mut()
-            },
-            Err(error) => error.into()
-        }
-    }
+    guard("sffi_lib_open", || unsafe {
+        check_null!(r_handle, name);
+        open(r_handle, name, false)
+    })
+}
+
+/// # Safety
+///
+/// `r_handle` must be writable and `name` must be a NUL-terminated string. Loading runs the library's initializers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_lib_open_now(r_handle: *mut *mut LibHandle, name: *const c_char) -> *mut CError {
+    guard("sffi_lib_open_now", || unsafe {
+        check_null!(r_handle, name);
+        open(r_handle, name, true)
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_lib_symbol(r_symbol: *mut *const c_void, handle: *const LibHandle, name: *const c_char) -> *mut CError {
-    unsafe {
+    guard("sffi_lib_symbol", || unsafe {
+        check_null!(r_symbol, handle, name);
+        let handle = resolve!(Library, handle);
         match (*handle)._symbol(name) {
             Ok(symbol) => { *r_symbol = symbol; null_mut() },
             Err(error) => error.into()
         }
-    }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_lib_func(r_func: *mut *const FuncHandle, handle: *mut LibHandle, name: *const c_char, desc: *const c_char) -> *mut CError {
-    unsafe {
+    guard("sffi_lib_func", || unsafe {
+        check_null!(r_func, handle, name, desc);
+        let library = resolve!(Library, handle);
         let desc = try_c_const_char_to_str(desc);
-        let desc = if let Some(desc) = desc { desc } else { return Error::InvalidDescriptor(Some("Invalid platform function descriptor".into())).into() };
-        match (*handle)._func(name, desc) {
+        let desc = if let Some(desc) = desc { desc } else { return Error::InvalidDescriptor { descriptor: None, message: Some("Invalid platform function descriptor".into()) }.into() };
+        let func = match (*library)._func(name, desc) { Ok(func) => func, Err(error) => return error.into() };
+        match register_in(Kind::Func, func as *mut FuncHandle, Kind::Library, handle) {
             Ok(func) => { *r_func = func; null_mut() },
             Err(error) => error.into()
         }
-    }
+    })
+}
+
+pub const SFFI_SYNTAX_SFFI    : c_uint = 0;
+pub const SFFI_SYNTAX_JNI     : c_uint = 1;
+pub const SFFI_SYNTAX_DYNCALL : c_uint = 2;
+
+/// # Safety
+///
+/// `r_func` must be writable, `handle` must be a live library handle, and `name` and `desc` must be NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_lib_func_ex(r_func: *mut *const FuncHandle, handle: *mut LibHandle, name: *const c_char, desc: *const c_char, syntax: c_uint) -> *mut CError {
+    guard("sffi_lib_func_ex", || unsafe {
+        check_null!(r_func, handle, name, desc);
+        let library = resolve!(Library, handle);
+        let syntax = match syntax {
+            SFFI_SYNTAX_SFFI => Syntax::Sffi,
+            SFFI_SYNTAX_JNI => Syntax::Jni,
+            SFFI_SYNTAX_DYNCALL => Syntax::Dyncall,
+            _ => return Error::InvalidArguments { index: None, message: Some(format!("Unknown descriptor syntax ({})", syntax)) }.into()
+        };
+        let desc = try_c_const_char_to_str(desc);
+        let desc = if let Some(desc) = desc { desc } else { return Error::InvalidDescriptor { descriptor: None, message: Some("Invalid platform function descriptor".into()) }.into() };
+        let func = match (*library)._func_with(name, desc, syntax) { Ok(func) => func, Err(error) => return error.into() };
+        match register_in(Kind::Func, func as *mut FuncHandle, Kind::Library, handle) {
+            Ok(func) => { *r_func = func; null_mut() },
+            Err(error) => error.into()
+        }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_lib_as_raw(handle: *const LibHandle) -> *mut c_void {
-    unsafe { (*handle).as_raw() }
+    guard_or("sffi_lib_as_raw", null_mut(), || unsafe {
+        if handle.is_null() { return null_mut() }
+        let Ok(handle) = resolve(Kind::Library, handle) else { return null_mut() };
+        (*handle).as_raw()
+    })
+}
+
+/// # Safety
+///
+/// `r_raw` must be writable and `handle` must be a live library handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_lib_as_raw_checked(r_raw: *mut *mut c_void, handle: *const LibHandle) -> *mut CError {
+    guard("sffi_lib_as_raw_checked", || unsafe {
+        check_null!(r_raw, handle);
+        let handle = resolve!(Library, handle);
+        *r_raw = (*handle).as_raw();
+        null_mut()
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_lib_close(handle: *mut LibHandle) {
+    guard_or("sffi_lib_close", (), || unsafe {
+        if handle.is_null() { return }
+        if let Ok(handle) = release(Kind::Library, handle) { drop_library(handle) }
+    })
+}
+
+/// # Safety
+///
+/// `handle` must be a live library handle. Functions and symbols taken from it must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_lib_close_checked(handle: *mut LibHandle) -> *mut CError {
+    guard("sffi_lib_close_checked", || unsafe {
+        check_null!(handle);
+        match release(Kind::Library, handle) {
+            Ok(handle) => { drop_library(handle); null_mut() },
+            Err(error) => error.into()
+        }
+    })
+}
+
+unsafe fn drop_library(handle: *mut LibHandle) {
     unsafe {
+        untrack(handle as *const c_void);
         drop_in_place(handle);
         dealloc(handle as *mut _, Layout::new::<LibHandle>());
     }
-}
\ No newline at end of file
+}
+
+unsafe fn open(r_handle: *mut *mut LibHandle, name: *const c_char, now: bool) -> *mut CError {
+    unsafe {
+        match LibHandle::_open(name, now) {
+            Ok(handle_) => {
+                let handle = alloc(Layout::new::<LibHandle>()) as *mut LibHandle;
+                handle.copy_from_nonoverlapping(&handle_, 1);
+                forget(handle_);
+                track(Resource::Library, handle as *const c_void);
+                *r_handle = register(Kind::Library, handle);
+                null_mut()
+            },
+            Err(error) => error.into()
+        }
+    }
+}
@@ -1,5 +1,28 @@
+macro_rules! check_null {
+    ($($argument:ident),+) => {
+        $( if $argument.is_null() { return $crate::api::error::null_argument(stringify!($argument)) } )+
+    };
+}
+
+macro_rules! resolve {
+    ($kind:ident, $handle:ident) => {
+        match $crate::api::handle::resolve($crate::api::handle::Kind::$kind, $handle) {
+            Ok(value) => value,
+            Err(error) => return error.into(),
+        }
+    };
+}
+
 pub mod adapter;
+pub mod allocator;
+pub mod debug;
 pub mod error;
+pub mod handle;
 pub mod interface;
 pub mod library;
-pub mod structure;
\ No newline at end of file
+pub mod status;
+pub mod structure;
+pub mod version;
+
+#[cfg(test)]
+pub(crate) static TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
\ No newline at end of file
@@ -0,0 +1,165 @@
+use crate::adapter::Adapter;
+use crate::api::adapter::{sffi_adapter_bind, sffi_adapter_call, sffi_adapter_free_checked, sffi_adapter_get, sffi_adapter_parse, sffi_adapter_set, sffi_adapter_set_leaked, sffi_adapter_set_strict_checked, sffi_bound_adapter_call, sffi_bound_adapter_free_checked};
+use crate::api::allocator::{sffi_set_allocator, AllocFn, FreeFn};
+use crate::api::error::{guard_or, sffi_error_code, sffi_error_free, sffi_error_msg, CError, SFFI_NO_ERR, SFFI_PANIC_ERR};
+use crate::api::handle::sffi_set_handle_checking;
+use crate::api::interface::{sffi_func_arg_count, sffi_func_arg_struct, sffi_func_arg_type, sffi_func_as_raw_checked, sffi_func_call_checked, sffi_func_call_raw_checked, sffi_func_desc, sffi_func_ret_struct, sffi_func_ret_type};
+use crate::api::library::{sffi_lib_as_raw_checked, sffi_lib_close_checked, sffi_lib_func, sffi_lib_func_ex, sffi_lib_open, sffi_lib_open_now, sffi_lib_symbol};
+use crate::api::structure::{sffi_struct_array_alloc, sffi_struct_array_element, sffi_struct_array_get_raw, sffi_struct_array_set_raw, sffi_struct_calloc_checked, sffi_struct_get_f32, sffi_struct_get_f64, sffi_struct_get_i16, sffi_struct_get_i32, sffi_struct_get_i64, sffi_struct_get_i8, sffi_struct_get_isize, sffi_struct_get_ptr, sffi_struct_get_u16, sffi_struct_get_u32, sffi_struct_get_u64, sffi_struct_get_u8, sffi_struct_get_usize, sffi_struct_set_f32, sffi_struct_set_f64, sffi_struct_set_i16, sffi_struct_set_i32, sffi_struct_set_i64, sffi_struct_set_i8, sffi_struct_set_isize, sffi_struct_set_ptr, sffi_struct_set_u16, sffi_struct_set_u32, sffi_struct_set_u64, sffi_struct_set_u8, sffi_struct_set_usize, sffi_struct_get_raw_checked, sffi_struct_malloc_checked, sffi_struct_set_raw_checked, sffi_struct_type_align_checked, sffi_struct_type_alloc, sffi_struct_type_field, sffi_struct_type_field_count, sffi_struct_type_field_struct, sffi_struct_type_free_checked, sffi_struct_type_size_checked};
+use crate::bound::BoundAdapter;
+use crate::interface::FuncHandle;
+use crate::library::LibHandle;
+use crate::structure::StructType;
+use libffi::raw::ffi_raw;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_uint, c_void, CStr, CString};
+use std::ptr::null;
+
+thread_local! {
+    static LAST_ERROR: RefCell<(c_uint, Option<CString>)> = const { RefCell::new((SFFI_NO_ERR, None)) };
+}
+
+fn store(error: *mut CError) -> c_uint {
+    unsafe {
+        let code = sffi_error_code(error);
+        let message = sffi_error_msg(error);
+        let message = if message.is_null() { None } else { Some(CStr::from_ptr(message).to_owned()) };
+        sffi_error_free(error);
+        LAST_ERROR.with(|it| *it.borrow_mut() = (code, message));
+        code
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_last_error_code() -> c_uint {
+    guard_or("sffi_last_error_code", SFFI_PANIC_ERR, || LAST_ERROR.with(|it| it.borrow().0))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_last_error_msg() -> *const c_char {
+    guard_or("sffi_last_error_msg", null(), || LAST_ERROR.with(|it| it.borrow().1.as_ref().map_or(null(), |it| it.as_ptr())))
+}
+
+macro_rules! status {
+    ($($name:ident => $func:ident($($argument:ident: $type:ty),*);)+) => {
+        $(
+            /// # Safety
+            ///
+            /// Same contract as the checked function it wraps.
+            #[unsafe(no_mangle)]
+            pub unsafe extern "C" fn $name($($argument: $type),*) -> c_uint {
+                guard_or(stringify!($name), SFFI_PANIC_ERR, || unsafe { store($func($($argument),*)) })
+            }
+        )+
+    };
+}
+
+status! {
+    sffi_lib_open_status => sffi_lib_open(r_handle: *mut *mut LibHandle, name: *const c_char);
+    sffi_lib_open_now_status => sffi_lib_open_now(r_handle: *mut *mut LibHandle, name: *const c_char);
+    sffi_lib_symbol_status => sffi_lib_symbol(r_symbol: *mut *const c_void, handle: *const LibHandle, name: *const c_char);
+    sffi_lib_func_status => sffi_lib_func(r_func: *mut *const FuncHandle, handle: *mut LibHandle, name: *const c_char, desc: *const c_char);
+    sffi_lib_func_ex_status => sffi_lib_func_ex(r_func: *mut *const FuncHandle, handle: *mut LibHandle, name: *const c_char, desc: *const c_char, syntax: c_uint);
+    sffi_lib_as_raw_status => sffi_lib_as_raw_checked(r_raw: *mut *mut c_void, handle: *const LibHandle);
+    sffi_lib_close_status => sffi_lib_close_checked(handle: *mut LibHandle);
+
+    sffi_func_call_status => sffi_func_call_checked(func: *const FuncHandle, rvalue: *mut c_void, avalue: *mut *mut c_void);
+    sffi_func_call_raw_status => sffi_func_call_raw_checked(func: *const FuncHandle, rvalue: *mut c_void, avalue: *mut ffi_raw);
+    sffi_func_as_raw_status => sffi_func_as_raw_checked(r_raw: *mut *const c_void, func: *const FuncHandle);
+    sffi_func_arg_count_status => sffi_func_arg_count(r_count: *mut c_uint, func: *const FuncHandle);
+    sffi_func_arg_type_status => sffi_func_arg_type(r_kind: *mut c_uint, r_size: *mut c_uint, r_align: *mut c_uint, func: *const FuncHandle, index: c_uint);
+    sffi_func_ret_type_status => sffi_func_ret_type(r_kind: *mut c_uint, r_size: *mut c_uint, r_align: *mut c_uint, func: *const FuncHandle);
+    sffi_func_arg_struct_status => sffi_func_arg_struct(r_struct_type: *mut *mut StructType, func: *const FuncHandle, index: c_uint);
+    sffi_func_ret_struct_status => sffi_func_ret_struct(r_struct_type: *mut *mut StructType, func: *const FuncHandle);
+    sffi_func_desc_status => sffi_func_desc(r_desc: *mut *mut c_char, func: *const FuncHandle);
+
+    sffi_adapter_parse_status => sffi_adapter_parse(r_adapter: *mut *mut Adapter, desc: *const c_char);
+    sffi_adapter_set_strict_status => sffi_adapter_set_strict_checked(adapter: *mut Adapter, strict: bool);
+    sffi_adapter_set_leaked_status => sffi_adapter_set_leaked(adapter: *mut Adapter, index: c_uint, leaked: bool);
+    sffi_adapter_call_status => sffi_adapter_call(adapter: *const Adapter, func: *const FuncHandle, rvalue: *mut c_void, argc: c_uint, argv: *mut *mut c_void);
+    sffi_adapter_set_status => sffi_adapter_set(adapter: *const Adapter, struct_type: *const StructType, structure: *mut c_void, index: c_uint, avalue: *const c_void);
+    sffi_adapter_get_status => sffi_adapter_get(adapter: *const Adapter, struct_type: *const StructType, structure: *const c_void, index: c_uint, rvalue: *mut c_void);
+    sffi_adapter_free_status => sffi_adapter_free_checked(adapter: *mut Adapter);
+    sffi_adapter_bind_status => sffi_adapter_bind(r_bound: *mut *mut BoundAdapter<'static>, adapter: *const Adapter, func: *const FuncHandle);
+    sffi_bound_adapter_call_status => sffi_bound_adapter_call(bound: *const BoundAdapter<'static>, rvalue: *mut c_void, argc: c_uint, argv: *mut *mut c_void);
+    sffi_bound_adapter_free_status => sffi_bound_adapter_free_checked(bound: *mut BoundAdapter<'static>);
+
+    sffi_struct_type_alloc_status => sffi_struct_type_alloc(r_struct_type: *mut *mut StructType, desc: *const c_char);
+    sffi_struct_type_size_status => sffi_struct_type_size_checked(r_size: *mut c_uint, struct_type: *const StructType);
+    sffi_struct_type_align_status => sffi_struct_type_align_checked(r_align: *mut c_uint, struct_type: *const StructType);
+    sffi_struct_type_field_count_status => sffi_struct_type_field_count(r_count: *mut c_uint, struct_type: *const StructType);
+    sffi_struct_type_field_status => sffi_struct_type_field(r_kind: *mut c_uint, r_offset: *mut c_uint, r_size: *mut c_uint, r_align: *mut c_uint, struct_type: *const StructType, index: c_uint);
+    sffi_struct_type_field_struct_status => sffi_struct_type_field_struct(r_struct_type: *mut *mut StructType, struct_type: *const StructType, index: c_uint);
+    sffi_struct_malloc_status => sffi_struct_malloc_checked(r_structure: *mut *mut c_void, struct_type: *const StructType);
+    sffi_struct_calloc_status => sffi_struct_calloc_checked(r_structure: *mut *mut c_void, struct_type: *const StructType);
+    sffi_struct_set_raw_status => sffi_struct_set_raw_checked(struct_type: *const StructType, structure: *mut c_void, index: c_uint, avalue: *const c_void);
+    sffi_struct_get_raw_status => sffi_struct_get_raw_checked(struct_type: *const StructType, structure: *const c_void, index: c_uint, rvalue: *mut c_void);
+    sffi_struct_type_free_status => sffi_struct_type_free_checked(struct_type: *mut StructType);
+    sffi_struct_array_alloc_status => sffi_struct_array_alloc(r_array: *mut *mut c_void, struct_type: *const StructType, count: usize);
+    sffi_struct_array_element_status => sffi_struct_array_element(r_element: *mut *mut c_void, struct_type: *const StructType, array: *mut c_void, count: usize, element: usize);
+    sffi_struct_array_set_raw_status => sffi_struct_array_set_raw(struct_type: *const StructType, array: *mut c_void, count: usize, element: usize, index: c_uint, avalue: *const c_void);
+    sffi_struct_array_get_raw_status => sffi_struct_array_get_raw(struct_type: *const StructType, array: *const c_void, count: usize, element: usize, index: c_uint, rvalue: *mut c_void);
+
+    sffi_struct_get_i8_status => sffi_struct_get_i8(r_value: *mut i8, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_i8_status => sffi_struct_set_i8(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: i8);
+    sffi_struct_get_i16_status => sffi_struct_get_i16(r_value: *mut i16, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_i16_status => sffi_struct_set_i16(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: i16);
+    sffi_struct_get_i32_status => sffi_struct_get_i32(r_value: *mut i32, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_i32_status => sffi_struct_set_i32(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: i32);
+    sffi_struct_get_i64_status => sffi_struct_get_i64(r_value: *mut i64, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_i64_status => sffi_struct_set_i64(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: i64);
+    sffi_struct_get_u8_status => sffi_struct_get_u8(r_value: *mut u8, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_u8_status => sffi_struct_set_u8(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: u8);
+    sffi_struct_get_u16_status => sffi_struct_get_u16(r_value: *mut u16, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_u16_status => sffi_struct_set_u16(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: u16);
+    sffi_struct_get_u32_status => sffi_struct_get_u32(r_value: *mut u32, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_u32_status => sffi_struct_set_u32(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: u32);
+    sffi_struct_get_u64_status => sffi_struct_get_u64(r_value: *mut u64, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_u64_status => sffi_struct_set_u64(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: u64);
+    sffi_struct_get_isize_status => sffi_struct_get_isize(r_value: *mut isize, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_isize_status => sffi_struct_set_isize(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: isize);
+    sffi_struct_get_usize_status => sffi_struct_get_usize(r_value: *mut usize, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_usize_status => sffi_struct_set_usize(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: usize);
+    sffi_struct_get_f32_status => sffi_struct_get_f32(r_value: *mut f32, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_f32_status => sffi_struct_set_f32(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: f32);
+    sffi_struct_get_f64_status => sffi_struct_get_f64(r_value: *mut f64, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_f64_status => sffi_struct_set_f64(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: f64);
+    sffi_struct_get_ptr_status => sffi_struct_get_ptr(r_value: *mut *mut c_void, struct_type: *const StructType, structure: *const c_void, index: c_uint);
+    sffi_struct_set_ptr_status => sffi_struct_set_ptr(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: *mut c_void);
+
+    sffi_set_allocator_status => sffi_set_allocator(alloc: Option<AllocFn>, free: Option<FreeFn>, data: *mut c_void);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_set_handle_checking_status(enabled: bool) -> c_uint {
+    guard_or("sffi_set_handle_checking_status", SFFI_PANIC_ERR, || store(sffi_set_handle_checking(enabled)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::error::{SFFI_INVALID_ARGUMENTS_ERR, SFFI_INVALID_DESCRIPTOR_ERR};
+    use crate::api::TEST_LOCK;
+    use std::ptr::null_mut;
+
+    #[test]
+    fn test_last_error() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let mut struct_type = null_mut::<StructType>();
+            assert_eq!(sffi_struct_type_alloc_status(&mut struct_type, c"[i32,q32]".as_ptr()), SFFI_INVALID_DESCRIPTOR_ERR);
+            assert_eq!(sffi_last_error_code(), SFFI_INVALID_DESCRIPTOR_ERR);
+            assert_eq!(CStr::from_ptr(sffi_last_error_msg()), c"Unknown type: q32");
+
+            assert_eq!(sffi_struct_type_alloc_status(&mut struct_type, c"[i32,i64]".as_ptr()), SFFI_NO_ERR);
+            assert_eq!(sffi_last_error_code(), SFFI_NO_ERR);
+            assert!(sffi_last_error_msg().is_null());
+
+            let mut size = 0;
+            assert_eq!(sffi_struct_type_size_status(&mut size, struct_type), SFFI_NO_ERR);
+            assert_eq!(size, 16);
+            assert_eq!(sffi_struct_type_free_status(struct_type), SFFI_NO_ERR);
+            assert_eq!(sffi_struct_type_size_status(null_mut(), null()), SFFI_INVALID_ARGUMENTS_ERR);
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::api::error::CError;
+use crate::api::error::{guard, guard_or, CError};
+use crate::tracking::{track, untrack, Resource};
+use crate::api::handle::{register, release, resolve, Kind};
 use crate::error::Error;
 use crate::internal::try_c_const_char_to_str;
 use crate::structure::StructType;
@@ -9,56 +11,493 @@ use std::ptr::{drop_in_place, null_mut};
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_type_alloc(r_struct_type: *mut *mut StructType, desc: *const c_char) -> *mut CError {
-    unsafe {
+    guard("sffi_struct_type_alloc", || unsafe {
+        check_null!(r_struct_type, desc);
         let desc = try_c_const_char_to_str(desc);
-        let desc = if let Some(desc) = desc { desc } else { return Error::InvalidDescriptor(Some("Invalid structure descriptor".into())).into(); };
+        let desc = if let Some(desc) = desc { desc } else { return Error::InvalidDescriptor { descriptor: None, message: Some("Invalid structure descriptor".into()) }.into(); };
         match StructType::from_str(desc) {
             Ok(struct_type_) => {
                 let struct_type = alloc(Layout::new::<StructType>()) as *mut StructType;
                 struct_type.copy_from_nonoverlapping(&struct_type_, 1);
                 forget(struct_type_);
-                *r_struct_type = struct_type;
+                track(Resource::StructType, struct_type as *const c_void);
+                *r_struct_type = register(Kind::StructType, struct_type);
                 null_mut()
             },
             Err(error) => error.into()
         }
-    }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_type_size(struct_type: *const StructType) -> c_uint {
-    unsafe { (*struct_type).size() }
+    guard_or("sffi_struct_type_size", 0, || unsafe {
+        if struct_type.is_null() { return 0 }
+        let Ok(struct_type) = resolve(Kind::StructType, struct_type) else { return 0 };
+        (*struct_type).size()
+    })
+}
+
+/// # Safety
+///
+/// `r_size` must be writable and `struct_type` must be a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_size_checked(r_size: *mut c_uint, struct_type: *const StructType) -> *mut CError {
+    guard("sffi_struct_type_size_checked", || unsafe {
+        check_null!(r_size, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        *r_size = (*struct_type).size();
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `struct_type` must be NULL or a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_align(struct_type: *const StructType) -> c_uint {
+    guard_or("sffi_struct_type_align", 0, || unsafe {
+        if struct_type.is_null() { return 0 }
+        let Ok(struct_type) = resolve(Kind::StructType, struct_type) else { return 0 };
+        (*struct_type).align()
+    })
+}
+
+/// # Safety
+///
+/// `r_align` must be writable and `struct_type` must be a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_align_checked(r_align: *mut c_uint, struct_type: *const StructType) -> *mut CError {
+    guard("sffi_struct_type_align_checked", || unsafe {
+        check_null!(r_align, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        *r_align = (*struct_type).align();
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `r_count` must be writable and `struct_type` must be a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_field_count(r_count: *mut c_uint, struct_type: *const StructType) -> *mut CError {
+    guard("sffi_struct_type_field_count", || unsafe {
+        check_null!(r_count, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        *r_count = (*struct_type).field_count() as c_uint;
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// The `r_*` outputs must be writable and `struct_type` must be a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_field(r_kind: *mut c_uint, r_offset: *mut c_uint, r_size: *mut c_uint, r_align: *mut c_uint, struct_type: *const StructType, index: c_uint) -> *mut CError {
+    guard("sffi_struct_type_field", || unsafe {
+        check_null!(r_kind, r_offset, r_size, r_align, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        if let Err(error) = check_index(&*struct_type, index) { return error.into() }
+        let (field, offset) = &(*struct_type).fields()[index as usize];
+        *r_kind = field.kind() as c_uint;
+        *r_offset = *offset;
+        *r_size = field.size();
+        *r_align = field.align();
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `r_struct_type` must be writable and `struct_type` must be a live struct type. The returned struct type is owned by the caller.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_field_struct(r_struct_type: *mut *mut StructType, struct_type: *const StructType, index: c_uint) -> *mut CError {
+    guard("sffi_struct_type_field_struct", || unsafe {
+        check_null!(r_struct_type, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        if let Err(error) = check_index(&*struct_type, index) { return error.into() }
+        let (field, _) = &(*struct_type).fields()[index as usize];
+        match field.as_struct() {
+            Some(field) => { *r_struct_type = copy_struct_type(field); null_mut() },
+            None => Error::InvalidArguments { index: None, message: Some(format!("Field {} of type '{}' is not a structure", index, field)) }.into()
+        }
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_malloc(struct_type: *const StructType) -> *mut c_void {
-    unsafe { (*struct_type).malloc() }
+    guard_or("sffi_struct_malloc", null_mut(), || unsafe {
+        if struct_type.is_null() { return null_mut() }
+        let Ok(struct_type) = resolve(Kind::StructType, struct_type) else { return null_mut() };
+        let structure = (*struct_type).malloc();
+        track(Resource::Struct, structure);
+        structure
+    })
+}
+
+/// # Safety
+///
+/// `r_structure` must be writable and `struct_type` must be a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_malloc_checked(r_structure: *mut *mut c_void, struct_type: *const StructType) -> *mut CError {
+    guard("sffi_struct_malloc_checked", || unsafe {
+        check_null!(r_structure, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        *r_structure = match (*struct_type).try_malloc() { Ok(structure) => structure, Err(error) => return error.into() };
+        track(Resource::Struct, *r_structure);
+        null_mut()
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_calloc(struct_type: *const StructType) -> *mut c_void {
-    unsafe { (*struct_type).calloc() }
+    guard_or("sffi_struct_calloc", null_mut(), || unsafe {
+        if struct_type.is_null() { return null_mut() }
+        let Ok(struct_type) = resolve(Kind::StructType, struct_type) else { return null_mut() };
+        let structure = (*struct_type).calloc();
+        track(Resource::Struct, structure);
+        structure
+    })
+}
+
+/// # Safety
+///
+/// `r_structure` must be writable and `struct_type` must be a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_calloc_checked(r_structure: *mut *mut c_void, struct_type: *const StructType) -> *mut CError {
+    guard("sffi_struct_calloc_checked", || unsafe {
+        check_null!(r_structure, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        *r_structure = match (*struct_type).try_calloc() { Ok(structure) => structure, Err(error) => return error.into() };
+        track(Resource::Struct, *r_structure);
+        null_mut()
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_set_raw(struct_type: *const StructType, structure: *mut c_void, index: c_uint, avalue: *const c_void) {
-    unsafe { (*struct_type).set_raw(structure, index, avalue); }
+    guard_or("sffi_struct_set_raw", (), || unsafe {
+        if struct_type.is_null() || structure.is_null() || avalue.is_null() { return }
+        let Ok(struct_type) = resolve(Kind::StructType, struct_type) else { return };
+        (*struct_type).set_raw(structure, index, avalue);
+    })
+}
+
+/// # Safety
+///
+/// `structure` must point to a writable instance of `struct_type` and `avalue` must hold a value of the field's type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_set_raw_checked(struct_type: *const StructType, structure: *mut c_void, index: c_uint, avalue: *const c_void) -> *mut CError {
+    guard("sffi_struct_set_raw_checked", || unsafe {
+        check_null!(struct_type, structure, avalue);
+        let struct_type = resolve!(StructType, struct_type);
+        if let Err(error) = check_index(&*struct_type, index) { return error.into() }
+        (*struct_type).set_raw(structure, index, avalue);
+        null_mut()
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_get_raw(struct_type: *const StructType, structure: *const c_void, index: c_uint, rvalue: *mut c_void) {
-    unsafe { (*struct_type).get_raw(structure, index, rvalue); }
+    guard_or("sffi_struct_get_raw", (), || unsafe {
+        if struct_type.is_null() || structure.is_null() || rvalue.is_null() { return }
+        let Ok(struct_type) = resolve(Kind::StructType, struct_type) else { return };
+        (*struct_type).get_raw(structure, index, rvalue);
+    })
+}
+
+/// # Safety
+///
+/// `structure` must point to an instance of `struct_type` and `rvalue` must have room for the field's type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_get_raw_checked(struct_type: *const StructType, structure: *const c_void, index: c_uint, rvalue: *mut c_void) -> *mut CError {
+    guard("sffi_struct_get_raw_checked", || unsafe {
+        check_null!(struct_type, structure, rvalue);
+        let struct_type = resolve!(StructType, struct_type);
+        if let Err(error) = check_index(&*struct_type, index) { return error.into() }
+        (*struct_type).get_raw(structure, index, rvalue);
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `struct_type` must be NULL or a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_stride(struct_type: *const StructType) -> c_uint {
+    guard_or("sffi_struct_type_stride", 0, || unsafe {
+        if struct_type.is_null() { return 0 }
+        let Ok(struct_type) = resolve(Kind::StructType, struct_type) else { return 0 };
+        (*struct_type).stride()
+    })
+}
+
+/// # Safety
+///
+/// `r_array` must be writable and `struct_type` must be a live struct type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_array_alloc(r_array: *mut *mut c_void, struct_type: *const StructType, count: usize) -> *mut CError {
+    guard("sffi_struct_array_alloc", || unsafe {
+        check_null!(r_array, struct_type);
+        let struct_type = resolve!(StructType, struct_type);
+        let array = match (*struct_type).try_alloc_array(count) { Ok(array) => array, Err(error) => return error.into() };
+        track(Resource::Struct, array);
+        *r_array = array;
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `r_element` must be writable and `array` must hold `count` elements of `struct_type`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_array_element(r_element: *mut *mut c_void, struct_type: *const StructType, array: *mut c_void, count: usize, element: usize) -> *mut CError {
+    guard("sffi_struct_array_element", || unsafe {
+        check_null!(r_element, struct_type, array);
+        let struct_type = resolve!(StructType, struct_type);
+        if let Err(error) = check_element(count, element) { return error.into() }
+        *r_element = (*struct_type).element(array, element) as *mut c_void;
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `array` must hold `count` writable elements of `struct_type` and `avalue` must hold a value of the field's type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_array_set_raw(struct_type: *const StructType, array: *mut c_void, count: usize, element: usize, index: c_uint, avalue: *const c_void) -> *mut CError {
+    guard("sffi_struct_array_set_raw", || unsafe {
+        check_null!(struct_type, array, avalue);
+        let struct_type = resolve!(StructType, struct_type);
+        if let Err(error) = check_element(count, element) { return error.into() }
+        if let Err(error) = check_index(&*struct_type, index) { return error.into() }
+        (*struct_type).set_raw((*struct_type).element(array, element) as *mut c_void, index, avalue);
+        null_mut()
+    })
+}
+
+/// # Safety
+///
+/// `array` must hold `count` elements of `struct_type` and `rvalue` must have room for the field's type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_array_get_raw(struct_type: *const StructType, array: *const c_void, count: usize, element: usize, index: c_uint, rvalue: *mut c_void) -> *mut CError {
+    guard("sffi_struct_array_get_raw", || unsafe {
+        check_null!(struct_type, array, rvalue);
+        let struct_type = resolve!(StructType, struct_type);
+        if let Err(error) = check_element(count, element) { return error.into() }
+        if let Err(error) = check_index(&*struct_type, index) { return error.into() }
+        (*struct_type).get_raw((*struct_type).element(array, element), index, rvalue);
+        null_mut()
+    })
+}
+
+macro_rules! typed {
+    ($($get:ident, $set:ident => $rust_get:ident, $rust_set:ident: $type:ty;)+) => {
+        $(
+            /// # Safety
+            ///
+            /// `r_value` must be writable and `structure` must point to an instance of `struct_type`.
+            #[unsafe(no_mangle)]
+            pub unsafe extern "C" fn $get(r_value: *mut $type, struct_type: *const StructType, structure: *const c_void, index: c_uint) -> *mut CError {
+                guard(stringify!($get), || unsafe {
+                    check_null!(r_value, struct_type, structure);
+                    let struct_type = resolve!(StructType, struct_type);
+                    match (*struct_type).$rust_get(structure, index as usize) {
+                        Ok(value) => { *r_value = value; null_mut() },
+                        Err(error) => error.into()
+                    }
+                })
+            }
+
+            /// # Safety
+            ///
+            /// `structure` must point to a writable instance of `struct_type`.
+            #[unsafe(no_mangle)]
+            pub unsafe extern "C" fn $set(struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: $type) -> *mut CError {
+                guard(stringify!($set), || unsafe {
+                    check_null!(struct_type, structure);
+                    let struct_type = resolve!(StructType, struct_type);
+                    match (*struct_type).$rust_set(structure, index as usize, value) {
+                        Ok(_) => null_mut(),
+                        Err(error) => error.into()
+                    }
+                })
+            }
+        )+
+    };
+}
+
+typed! {
+    sffi_struct_get_i8, sffi_struct_set_i8 => get_i8, set_i8: i8;
+    sffi_struct_get_i16, sffi_struct_set_i16 => get_i16, set_i16: i16;
+    sffi_struct_get_i32, sffi_struct_set_i32 => get_i32, set_i32: i32;
+    sffi_struct_get_i64, sffi_struct_set_i64 => get_i64, set_i64: i64;
+    sffi_struct_get_u8, sffi_struct_set_u8 => get_u8, set_u8: u8;
+    sffi_struct_get_u16, sffi_struct_set_u16 => get_u16, set_u16: u16;
+    sffi_struct_get_u32, sffi_struct_set_u32 => get_u32, set_u32: u32;
+    sffi_struct_get_u64, sffi_struct_set_u64 => get_u64, set_u64: u64;
+    sffi_struct_get_isize, sffi_struct_set_isize => get_isize, set_isize: isize;
+    sffi_struct_get_usize, sffi_struct_set_usize => get_usize, set_usize: usize;
+    sffi_struct_get_f32, sffi_struct_set_f32 => get_f32, set_f32: f32;
+    sffi_struct_get_f64, sffi_struct_set_f64 => get_f64, set_f64: f64;
+    sffi_struct_get_ptr, sffi_struct_set_ptr => get_ptr, set_ptr: *mut c_void;
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_free(structure: *mut c_void) {
-    StructType::free(structure)
+    guard_or("sffi_struct_free", (), || {
+        untrack(structure);
+        StructType::free(structure)
+    })
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sffi_struct_type_free(struct_type: *mut StructType) {
+    guard_or("sffi_struct_type_free", (), || unsafe {
+        if struct_type.is_null() { return }
+        if let Ok(struct_type) = release(Kind::StructType, struct_type) { drop_struct_type(struct_type) }
+    })
+}
+
+/// # Safety
+///
+/// `struct_type` must be a live struct type and must not be used again once this returns successfully.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_struct_type_free_checked(struct_type: *mut StructType) -> *mut CError {
+    guard("sffi_struct_type_free_checked", || unsafe {
+        check_null!(struct_type);
+        match release(Kind::StructType, struct_type) {
+            Ok(struct_type) => { drop_struct_type(struct_type); null_mut() },
+            Err(error) => error.into()
+        }
+    })
+}
+
+pub(crate) fn copy_struct_type(struct_type_: &StructType) -> *mut StructType {
     unsafe {
+        let struct_type = alloc(Layout::new::<StructType>()) as *mut StructType;
+        struct_type.write(struct_type_.clone());
+        track(Resource::StructType, struct_type as *const c_void);
+        register(Kind::StructType, struct_type)
+    }
+}
+
+unsafe fn drop_struct_type(struct_type: *mut StructType) {
+    unsafe {
+        untrack(struct_type as *const c_void);
         drop_in_place(struct_type);
         dealloc(struct_type as *mut u8, Layout::new::<StructType>());
     }
 }
+
+fn check_element(count: usize, element: usize) -> Result<(), Error> {
+    if count <= element { return Error::invalid_args_from_string(format!("Invalid element ({} / {})", element, count)) }
+    Ok(())
+}
+
+fn check_index(struct_type: &StructType, index: c_uint) -> Result<(), Error> {
+    let count = struct_type.fields().len();
+    if count <= index as usize { return Error::invalid_args_from_string(format!("Invalid index ({} / {})", index, count)) }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::error::{sffi_error_code, sffi_error_free, SFFI_INVALID_ARGUMENTS_ERR, SFFI_INVALID_CAST_ERR};
+    use crate::api::interface::{SFFI_TYPE_F64, SFFI_TYPE_I8, SFFI_TYPE_STRUCT};
+    use crate::api::TEST_LOCK;
+
+    #[test]
+    fn test_introspection() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let mut struct_type = null_mut::<StructType>();
+            assert!(sffi_struct_type_alloc(&mut struct_type, c"[i8,[u16,f64]]".as_ptr()).is_null());
+            assert_eq!(sffi_struct_type_align(struct_type), 8);
+            let mut count = 0;
+            assert!(sffi_struct_type_field_count(&mut count, struct_type).is_null());
+            assert_eq!(count, 2);
+
+            let (mut kind, mut offset, mut size, mut align) = (0, 0, 0, 0);
+            assert!(sffi_struct_type_field(&mut kind, &mut offset, &mut size, &mut align, struct_type, 1).is_null());
+            assert_eq!((kind, offset, size, align), (SFFI_TYPE_STRUCT, 8, 16, 8));
+            let error = sffi_struct_type_field(&mut kind, &mut offset, &mut size, &mut align, struct_type, 2);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+
+            let mut nested = null_mut::<StructType>();
+            assert!(sffi_struct_type_field_struct(&mut nested, struct_type, 1).is_null());
+            assert!(sffi_struct_type_field(&mut kind, &mut offset, &mut size, &mut align, nested, 1).is_null());
+            assert_eq!((kind, offset, size, align), (SFFI_TYPE_F64, 8, 8, 8));
+            let error = sffi_struct_type_field_struct(&mut nested, struct_type, 0);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+            assert!(sffi_struct_type_field(&mut kind, &mut offset, &mut size, &mut align, struct_type, 0).is_null());
+            assert_eq!(kind, SFFI_TYPE_I8);
+
+            sffi_struct_type_free(nested);
+            sffi_struct_type_free(struct_type);
+        }
+    }
+    #[test]
+    fn test_typed() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let mut struct_type = null_mut::<StructType>();
+            assert!(sffi_struct_type_alloc(&mut struct_type, c"[u8,f64,*]".as_ptr()).is_null());
+            let structure = sffi_struct_calloc(struct_type);
+            assert!(sffi_struct_set_u8(struct_type, structure, 0, 200).is_null());
+            assert!(sffi_struct_set_f64(struct_type, structure, 1, 0.5).is_null());
+            assert!(sffi_struct_set_ptr(struct_type, structure, 2, structure).is_null());
+
+            let (mut byte, mut double, mut pointer) = (0u8, 0f64, null_mut::<c_void>());
+            assert!(sffi_struct_get_u8(&mut byte, struct_type, structure, 0).is_null());
+            assert!(sffi_struct_get_f64(&mut double, struct_type, structure, 1).is_null());
+            assert!(sffi_struct_get_ptr(&mut pointer, struct_type, structure, 2).is_null());
+            assert_eq!((byte, double, pointer), (200, 0.5, structure));
+
+            let error = sffi_struct_set_i32(struct_type, structure, 1, 1);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_CAST_ERR);
+            sffi_error_free(error);
+            let error = sffi_struct_get_u8(&mut byte, struct_type, structure, 3);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+            assert_eq!(double, 0.5);
+
+            sffi_struct_free(structure);
+            sffi_struct_type_free(struct_type);
+        }
+    }
+
+    #[test]
+    fn test_array() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        unsafe {
+            let mut struct_type = null_mut::<StructType>();
+            assert!(sffi_struct_type_alloc(&mut struct_type, c"[i64,i8]".as_ptr()).is_null());
+            assert_eq!(sffi_struct_type_stride(struct_type), 16);
+            let mut array = null_mut::<c_void>();
+            assert!(sffi_struct_array_alloc(&mut array, struct_type, 4).is_null());
+            assert!(sffi_struct_array_set_raw(struct_type, array, 4, 3, 1, &9i8 as *const i8 as *const c_void).is_null());
+            let mut value = 0i8;
+            assert!(sffi_struct_array_get_raw(struct_type, array, 4, 3, 1, &mut value as *mut i8 as *mut c_void).is_null());
+            assert_eq!(value, 9);
+
+            let mut element = null_mut::<c_void>();
+            assert!(sffi_struct_array_element(&mut element, struct_type, array, 4, 3).is_null());
+            assert!(sffi_struct_get_i8(&mut value, struct_type, element, 1).is_null());
+            assert_eq!(element as usize - array as usize, 48);
+
+            let error = sffi_struct_array_element(&mut element, struct_type, array, 4, 4);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+            let error = sffi_struct_array_alloc(&mut array, struct_type, usize::MAX);
+            assert_eq!(sffi_error_code(error), SFFI_INVALID_ARGUMENTS_ERR);
+            sffi_error_free(error);
+
+            sffi_struct_free(array);
+            sffi_struct_type_free(struct_type);
+        }
+    }
+}
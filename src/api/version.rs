@@ -0,0 +1,25 @@
+use crate::api::error::guard_or;
+use crate::internal::try_c_const_char_to_str;
+use crate::version::{has_feature, ABI_VERSION};
+use std::ffi::{c_char, c_uint};
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_version() -> *const c_char {
+    guard_or("sffi_version", c"".as_ptr(), || concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn sffi_abi_version() -> c_uint {
+    guard_or("sffi_abi_version", 0, || ABI_VERSION)
+}
+
+/// # Safety
+///
+/// `name` must be NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sffi_has_feature(name: *const c_char) -> bool {
+    guard_or("sffi_has_feature", false, || {
+        if name.is_null() { return false }
+        try_c_const_char_to_str(name).is_some_and(has_feature)
+    })
+}
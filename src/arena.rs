@@ -0,0 +1,151 @@
+use crate::allocator;
+use crate::error::Error;
+use crate::internal::strdup_c_const_char;
+use std::cell::{Cell, RefCell};
+use std::cmp::max;
+use std::ffi::{c_char, c_void, CStr};
+use std::marker::PhantomData;
+use std::ptr::{copy_nonoverlapping, null_mut};
+
+const CHUNK_SIZE: usize = 4096;
+const CHUNK_ALIGN: usize = 16;
+
+struct Arena {
+    chunks: Vec<(*mut u8, usize)>,
+    chunk: usize,
+    used: usize,
+}
+
+thread_local! {
+    static ARENA: RefCell<Arena> = const { RefCell::new(Arena { chunks: Vec::new(), chunk: 0, used: 0 }) };
+    static TEMPORARY: Cell<bool> = const { Cell::new(false) };
+}
+
+impl Arena {
+    fn alloc(&mut self, size: usize, align: usize) -> Result<*mut c_void, Error> {
+        let error = || Error::Allocation { size, align };
+        let align = max(align, 1);
+        loop {
+            if self.chunk == self.chunks.len() {
+                let capacity = self.chunks.last().map_or(CHUNK_SIZE, |it| it.1 * 2);
+                let capacity = max(capacity, size.checked_add(align).ok_or_else(error)?);
+                let base = allocator::alloc(capacity, CHUNK_ALIGN).map_err(|_| error())? as *mut u8;
+                self.chunks.push((base, capacity));
+            }
+
+            let (base, capacity) = self.chunks[self.chunk];
+            let start = (base as usize + self.used).next_multiple_of(align) - base as usize;
+            if let Some(end) = start.checked_add(size) && end <= capacity {
+                self.used = end;
+                return Ok(unsafe { base.add(start) } as *mut c_void)
+            }
+
+            if self.used == 0 {
+                // Too small even when empty, the unused chunks after it are replaced by a larger one
+                for (base, _) in self.chunks.drain(self.chunk..) {
+                    allocator::free(base as *mut c_void)
+                }
+            } else {
+                self.chunk += 1;
+                self.used = 0;
+            }
+        }
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        for &(base, _) in &self.chunks {
+            allocator::free(base as *mut c_void)
+        }
+    }
+}
+
+pub(crate) struct Scope {
+    chunk: usize,
+    used: usize,
+    _thread: PhantomData<*const ()>,
+}
+
+struct Mode(bool);
+
+impl Drop for Mode {
+    fn drop(&mut self) {
+        TEMPORARY.set(self.0)
+    }
+}
+
+impl Scope {
+    pub(crate) fn enter() -> Self {
+        ARENA.with_borrow(|it| Self { chunk: it.chunk, used: it.used, _thread: PhantomData })
+    }
+
+    pub(crate) fn alloc(&self, size: usize, align: usize) -> Result<*mut c_void, Error> {
+        ARENA.with_borrow_mut(|it| it.alloc(size, align))
+    }
+
+    pub(crate) fn temporary<R>(&self, conversion: impl FnOnce() -> R) -> R {
+        let _mode = Mode(TEMPORARY.replace(true));
+        conversion()
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        ARENA.with_borrow_mut(|it| {
+            it.chunk = self.chunk;
+            it.used = self.used;
+        })
+    }
+}
+
+pub(crate) fn leaked<R>(conversion: impl FnOnce() -> R) -> R {
+    let _mode = Mode(TEMPORARY.replace(false));
+    conversion()
+}
+
+pub(crate) fn strdup(text: *const c_char) -> *mut c_char {
+    if !TEMPORARY.get() { return strdup_c_const_char(text) }
+    let bytes = unsafe { CStr::from_ptr(text) }.to_bytes_with_nul();
+    let Ok(copy) = ARENA.with_borrow_mut(|it| it.alloc(bytes.len(), 1)) else { return null_mut() };
+    unsafe { copy_nonoverlapping(bytes.as_ptr(), copy as *mut u8, bytes.len()) }
+    copy as *mut c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope() {
+        let outer = Scope::enter();
+        let first = outer.alloc(3, 1).unwrap();
+        let aligned = outer.alloc(16, 16).unwrap();
+        assert_eq!(aligned as usize % 16, 0);
+
+        let inner = Scope::enter();
+        let second = inner.alloc(8, 8).unwrap();
+        let large = inner.alloc(CHUNK_SIZE * 3, 8).unwrap();
+        assert_ne!(second, large);
+        drop(inner);
+
+        // Released memory is reused, the outer temporaries stay in place
+        assert_eq!(outer.alloc(8, 8).unwrap(), second);
+        assert_ne!(first, second);
+        drop(outer);
+        assert_eq!(Scope::enter().alloc(3, 1).unwrap(), first);
+    }
+
+    #[test]
+    fn test_strdup() {
+        let scope = Scope::enter();
+        let copy = scope.temporary(|| strdup(c"text".as_ptr()));
+        assert_eq!(unsafe { CStr::from_ptr(copy) }, c"text");
+        let next = scope.alloc(1, 1).unwrap();
+        assert_eq!(next as usize, copy as usize + 5);
+
+        let owned = scope.temporary(|| leaked(|| strdup(c"text".as_ptr())));
+        assert_ne!(owned as usize, next as usize + 1);
+        unsafe { crate::api::adapter::sffi_string_free(owned) }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::adapter::Adapter;
+use crate::arena;
+use crate::arena::Scope;
+use crate::error::Error;
+use crate::interface::{FuncCif, FuncHandle, FuncType};
+use std::cmp::max;
+use std::ffi::{c_double, c_float, c_int, c_uint, c_void};
+use std::ptr::{copy_nonoverlapping, read, write};
+
+type Convert = unsafe fn(*const c_void, *mut c_void);
+
+enum Step {
+    Copy(usize),
+    Convert(Convert),
+    Cast(FuncType, FuncType),
+}
+
+pub struct BoundAdapter<'a> {
+    adapter: Adapter,
+    func: &'a FuncHandle,
+    cif: FuncCif,
+    arguments: Box<[(Step, usize)]>,
+    result: (Step, usize),
+    size: usize,
+    align: usize,
+}
+
+impl<'a> BoundAdapter<'a> {
+    pub(crate) fn new(adapter: &Adapter, func: &'a FuncHandle) -> Result<Self, Error> {
+        let target_arguments_types = adapter.desc().argument_types();
+        let func_arguments_types = func.desc().argument_types();
+        if target_arguments_types.len() != func_arguments_types.len() { return Error::invalid_args_from_string(format!("Function invalid arguments count ({} / {})", func_arguments_types.len(), target_arguments_types.len())) }
+
+        let (mut size, mut align) = (0usize, 1usize);
+        let mut place = |r#type: &FuncType| {
+            let layout = Adapter::_call_calc_type_layout(r#type);
+            let offset = size.next_multiple_of(layout.align());
+            size = offset + layout.size();
+            align = max(align, layout.align());
+            offset
+        };
+
+        let func_return_type = func.desc().return_type();
+        let result = (step(func_return_type, adapter.desc().return_type())?, place(func_return_type));
+        let arguments = target_arguments_types.iter().zip(func_arguments_types).enumerate()
+            .map(|(i, (target_type, func_type))| Ok((step(target_type, func_type).map_err(|it| it.with_argument(i))?, place(func_type))))
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { adapter: adapter.clone(), func, cif: FuncCif::new(func.desc())?, arguments, result, size, align })
+    }
+
+    pub fn func(&self) -> &FuncHandle {
+        self.func
+    }
+
+    pub fn call(&self, result: *mut c_void, arguments: &mut [*mut c_void]) -> Result<(), Error> {
+        unsafe { self._call(result, arguments.len() as c_uint, arguments.as_mut_ptr()) }
+    }
+
+    pub(crate) unsafe fn _call(&self, result: *mut c_void, argc: c_uint, argv: *mut *mut c_void) -> Result<(), Error> {
+        unsafe {
+            if argc as usize != self.arguments.len() { return Error::invalid_args_from_string(format!("Accepted invalid arguments count ({} / {})", argc, self.arguments.len())) }
+
+            let scope = Scope::enter();
+            let buffer = scope.alloc(self.size, self.align)?;
+            let func_arguments = scope.alloc(self.arguments.len() * size_of::<*mut c_void>(), align_of::<*mut c_void>())? as *mut *mut c_void;
+            scope.temporary(|| {
+                for (i, (step, offset)) in self.arguments.iter().enumerate() {
+                    let func_argument = buffer.byte_add(*offset);
+                    let run = || self._run(step, *argv.add(i), func_argument);
+                    if self.adapter.is_leaked(i as c_uint) { arena::leaked(run) } else { run() }.map_err(|it| it.with_argument(i))?;
+                    *func_arguments.add(i) = func_argument;
+                }
+                Ok::<_, Error>(())
+            })?;
+
+            let func_return = buffer.byte_add(self.result.1);
+            self.cif._call(self.func.as_raw(), func_return, func_arguments);
+
+            arena::leaked(|| self._run(&self.result.0, func_return, result))
+        }
+    }
+
+    unsafe fn _run(&self, step: &Step, from_addr: *const c_void, into_addr: *mut c_void) -> Result<(), Error> {
+        unsafe {
+            match step {
+                Step::Copy(size) => copy_nonoverlapping(from_addr as *const u8, into_addr as *mut u8, *size),
+                Step::Convert(convert) => convert(from_addr, into_addr),
+                Step::Cast(from_type, into_type) => return self.adapter._call_cast_type(from_type, into_type, from_addr, into_addr),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn step(from_type: &FuncType, into_type: &FuncType) -> Result<Step, Error> {
+    let cast = || Ok(Step::Cast(from_type.clone(), into_type.clone()));
+    let checked = |r#type: &FuncType| matches!(r#type, FuncType::Enum(_) | FuncType::Flags(_));
+
+    if (checked(into_type) && !from_type.is_auto()) || (checked(from_type) && !into_type.is_auto()) { return cast() }
+    if let (FuncType::Struct(_), _) | (_, FuncType::Struct(_)) = (from_type, into_type) && (from_type.is_auto() || into_type.is_auto()) {
+        return Error::invalid_cast(from_type, into_type)
+    }
+    if from_type.is_auto() || into_type.is_auto() {
+        let r#type = if from_type.is_auto() { into_type } else { from_type };
+        return Ok(Step::Copy(r#type.size() as usize))
+    }
+    if from_type.is_void() && into_type.is_void() { return Ok(Step::Copy(0)) }
+    if from_type.is_complex() || into_type.is_complex() {
+        let pair = |r#type: &FuncType| match r#type {
+            FuncType::ComplexF32 | FuncType::ComplexF64 => true,
+            FuncType::Struct(it) => Adapter::_is_complex_pair(it),
+            _ => false
+        };
+        let real = from_type.is_integer() || from_type.is_int128() || from_type.is_float();
+        if from_type == into_type || ((pair(from_type) || real) && pair(into_type)) { return cast() }
+        return Error::invalid_cast(from_type, into_type)
+    }
+    if let (FuncType::Struct(_), _) | (_, FuncType::Struct(_)) = (from_type, into_type) { return Error::invalid_cast(from_type, into_type) }
+    if from_type.is_int128() || into_type.is_int128() {
+        let wide = |r#type: &FuncType| r#type.is_integer() || r#type.is_int128() || r#type.is_float();
+        if wide(from_type) && wide(into_type) { return cast() }
+        return Error::invalid_cast(from_type, into_type)
+    }
+    if from_type.is_ptr() && into_type.is_ptr() {
+        if from_type.is_ref_str() && into_type.is_borrow_str() { return cast() }
+        return Ok(Step::Copy(size_of::<isize>()))
+    }
+    if let Some(convert) = converter(from_type, into_type) { return Ok(Step::Convert(convert)) }
+    Error::invalid_cast(from_type, into_type)
+}
+
+trait Number: Copy {
+    fn from_i64(value: i64) -> Self;
+    fn from_u64(value: u64) -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn cast<T: Number>(self) -> T;
+}
+
+macro_rules! numbers {
+    ($($type:ty => $wide:ty, $from:ident;)+) => {
+        $(
+            impl Number for $type {
+                fn from_i64(value: i64) -> Self { value as _ }
+                fn from_u64(value: u64) -> Self { value as _ }
+                fn from_f64(value: f64) -> Self { value as _ }
+                fn cast<T: Number>(self) -> T { T::$from(self as $wide) }
+            }
+        )+
+    };
+}
+
+numbers! {
+    i8 => i64, from_i64;
+    i16 => i64, from_i64;
+    i32 => i64, from_i64;
+    i64 => i64, from_i64;
+    isize => i64, from_i64;
+    u8 => u64, from_u64;
+    u16 => u64, from_u64;
+    u32 => u64, from_u64;
+    u64 => u64, from_u64;
+    usize => u64, from_u64;
+    f32 => f64, from_f64;
+    f64 => f64, from_f64;
+    // Long doubles are still passed around as `i128`
+    i128 => f64, from_f64;
+}
+
+unsafe fn convert<F: Number, T: Number>(from_addr: *const c_void, into_addr: *mut c_void) {
+    unsafe { write(into_addr as *mut T, read(from_addr as *const F).cast()) }
+}
+
+fn converter(from_type: &FuncType, into_type: &FuncType) -> Option<Convert> {
+    match from_type {
+        FuncType::Int           => converter_from::<c_int>(into_type),
+        FuncType::Float         => converter_from::<c_float>(into_type),
+        FuncType::Double        => converter_from::<c_double>(into_type),
+        FuncType::LongDouble    => converter_from::<i128>(into_type),
+        FuncType::ISize         => converter_from::<isize>(into_type),
+        FuncType::USize         => converter_from::<usize>(into_type),
+
+        FuncType::S8            => converter_from::<i8>(into_type),
+        FuncType::S16           => converter_from::<i16>(into_type),
+        FuncType::S32           => converter_from::<i32>(into_type),
+        FuncType::S64           => converter_from::<i64>(into_type),
+
+        FuncType::U8            => converter_from::<u8>(into_type),
+        FuncType::U16           => converter_from::<u16>(into_type),
+        FuncType::U32           => converter_from::<u32>(into_type),
+        FuncType::U64           => converter_from::<u64>(into_type),
+
+        FuncType::F32           => converter_from::<f32>(into_type),
+        FuncType::F64           => converter_from::<f64>(into_type),
+        FuncType::F128          => converter_from::<i128>(into_type),
+        _ => None
+    }
+}
+
+fn converter_from<F: Number>(into_type: &FuncType) -> Option<Convert> {
+    let convert: Convert = match into_type {
+        FuncType::Int           => convert::<F, c_int>,
+        FuncType::Float         => convert::<F, c_float>,
+        FuncType::Double        => convert::<F, c_double>,
+        FuncType::LongDouble    => convert::<F, i128>,
+        FuncType::ISize         => convert::<F, isize>,
+        FuncType::USize         => convert::<F, usize>,
+
+        FuncType::S8            => convert::<F, i8>,
+        FuncType::S16           => convert::<F, i16>,
+        FuncType::S32           => convert::<F, i32>,
+        FuncType::S64           => convert::<F, i64>,
+
+        FuncType::U8            => convert::<F, u8>,
+        FuncType::U16           => convert::<F, u16>,
+        FuncType::U32           => convert::<F, u32>,
+        FuncType::U64           => convert::<F, u64>,
+
+        FuncType::F32           => convert::<F, f32>,
+        FuncType::F64           => convert::<F, f64>,
+        FuncType::F128          => convert::<F, i128>,
+        _ => return None
+    };
+    Some(convert)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::FuncDesc;
+    use std::ffi::{c_char, CStr};
+
+    unsafe extern "C" fn scale(value: i64, factor: f64) -> f64 { value as f64 * factor }
+
+    #[test]
+    fn test_bind() {
+        let func = FuncHandle::new(scale as *const c_void, FuncDesc::from_str("(i64,f64)f64").unwrap()).unwrap();
+        let adapter = Adapter::from_str("(i32,u8)i16").unwrap();
+        let bound = adapter.bind(&func).unwrap();
+
+        let (value, factor) = (-3i32, 5u8);
+        let arguments = &mut [&value as *const i32 as *mut c_void, &factor as *const u8 as *mut c_void];
+        let (mut expected, mut result) = (0i16, 0i16);
+        adapter.call(&func, &mut expected as *mut i16 as *mut c_void, arguments).unwrap();
+        bound.call(&mut result as *mut i16 as *mut c_void, arguments).unwrap();
+        assert_eq!((expected, result), (-15, -15));
+        assert!(bound.call(&mut result as *mut i16 as *mut c_void, &mut arguments[..1]).is_err());
+
+        assert!(Adapter::from_str("(i32)i16").unwrap().bind(&func).is_err());
+        let error = Adapter::from_str("(*,f64)f64").unwrap().bind(&func).err().unwrap();
+        assert!(matches!(error, Error::Argument { index: 0, .. }));
+        let error = Adapter::from_str("(i64,f64)[f64,f64]").unwrap().bind(&func).err().unwrap();
+        assert!(matches!(error, Error::InvalidCast { .. }));
+        let pair = FuncHandle::new(scale as *const c_void, FuncDesc::from_str("(i64,f64)[f64,f64]").unwrap()).unwrap();
+        assert!(Adapter::from_str("(i64,f64)auto").unwrap().bind(&pair).is_err());
+        assert!(Adapter::from_str("(i64,f64)cf64").unwrap().bind(&pair).is_ok());
+    }
+
+    fn bytes<T>(value: T) -> u128 {
+        let mut bytes = 0u128;
+        unsafe { write(&mut bytes as *mut u128 as *mut T, value) }
+        bytes
+    }
+
+    macro_rules! samples {
+        ($($desc:literal: $type:ty = [$($value:expr),+];)+) => {
+            [$({
+                unsafe extern "C" fn identity(value: $type) -> $type { value }
+                ($desc, identity as *const c_void, vec![$(bytes::<$type>($value)),+])
+            }),+]
+        };
+    }
+
+    #[test]
+    fn test_mirror() {
+        let text = c"text".as_ptr();
+        let samples = samples! {
+            "int": c_int = [-3, c_int::MAX];
+            "float": c_float = [-2.5, 1e10];
+            "double": c_double = [-2.5, 1e30];
+            "isize": isize = [-3, isize::MAX];
+            "usize": usize = [200, usize::MAX];
+            "i8": i8 = [-3, i8::MAX];
+            "i16": i16 = [-3, i16::MAX];
+            "i32": i32 = [-3, i32::MAX];
+            "i64": i64 = [-3, i64::MAX];
+            "u8": u8 = [200, u8::MAX];
+            "u16": u16 = [200, u16::MAX];
+            "u32": u32 = [200, u32::MAX];
+            "u64": u64 = [200, u64::MAX];
+            "f32": f32 = [-2.5, 1e10];
+            "f64": f64 = [-2.5, 1e30];
+            "*": *const c_char = [text];
+            "&str": *const c_char = [text];
+            "*str": *const c_char = [text];
+        };
+
+        for (target, _, values) in &samples {
+            let adapter = Adapter::from_str(&format!("({target}){target}")).unwrap();
+            for (desc, func, _) in &samples {
+                let func = FuncHandle::new(*func, FuncDesc::from_str(&format!("({desc}){desc}")).unwrap()).unwrap();
+                let bound = adapter.bind(&func);
+                // Strings are compared by content, the ones copied for the result are owned by the caller
+                let read = |result: u128| unsafe {
+                    if !target.ends_with("str") { return result.to_ne_bytes().to_vec() }
+                    let content = CStr::from_ptr(result as usize as *const c_char).to_bytes().to_vec();
+                    if (*target, *desc) == ("*str", "&str") { crate::api::adapter::sffi_string_free(result as usize as *mut c_char) }
+                    content
+                };
+
+                for value in values {
+                    let arguments = &mut [value as *const u128 as *mut c_void];
+                    let (mut expected, mut result) = (0u128, 0u128);
+                    let unbound = adapter.call(&func, &mut expected as *mut u128 as *mut c_void, arguments);
+                    let Ok(bound) = &bound else {
+                        assert!(unbound.is_err(), "{target} -> {desc} fails to bind");
+                        continue
+                    };
+                    let expected = unbound.map(|_| read(expected));
+                    let result = bound.call(&mut result as *mut u128 as *mut c_void, arguments).map(|_| read(result));
+                    assert_eq!(expected.ok(), result.ok(), "{target} -> {desc}");
+                }
+            }
+        }
+
+        unsafe extern "C" fn nothing() {}
+        let func = FuncHandle::new(nothing as *const c_void, FuncDesc::from_str("()void").unwrap()).unwrap();
+        let adapter = Adapter::from_str("()void").unwrap();
+        adapter.call(&func, std::ptr::null_mut(), &mut []).unwrap();
+        adapter.bind(&func).unwrap().call(std::ptr::null_mut(), &mut []).unwrap();
+    }
+
+    #[test]
+    fn test_cast() {
+        unsafe extern "C" fn first(text: *mut c_char) -> c_char { unsafe { *text } }
+        let func = FuncHandle::new(first as *const c_void, FuncDesc::from_str("(*str)i8").unwrap()).unwrap();
+        let bound = Adapter::from_str("(&str)auto").unwrap().bind(&func).unwrap();
+
+        let text = c"text".as_ptr();
+        let mut result = 0i8;
+        bound.call(&mut result as *mut i8 as *mut c_void, &mut [&text as *const *const c_char as *mut c_void]).unwrap();
+        assert_eq!(result, b't' as i8);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::error::Error;
+use crate::interface::{FuncDesc, FuncType};
+use crate::structure::StructType;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Language {
+    C, Rust
+}
+
+struct Generator {
+    language: Language,
+    structs: Vec<(String, StructType)>,
+    output: String,
+}
+
+pub fn c_struct(name: &str, struct_type: &StructType) -> Result<String, Error> {
+    let mut generator = Generator::new(Language::C);
+    generator.structure(name, struct_type)?;
+    Ok(generator.output)
+}
+
+pub fn c_prototype(name: &str, desc: &str) -> Result<String, Error> {
+    c_prototype_from_desc(name, &FuncDesc::from_str(desc)?)
+}
+
+pub(crate) fn c_prototype_from_desc(name: &str, desc: &FuncDesc) -> Result<String, Error> {
+    let mut generator = Generator::new(Language::C);
+    generator.function(name, desc)?;
+    Ok(generator.output)
+}
+
+pub fn rust_struct(name: &str, struct_type: &StructType) -> Result<String, Error> {
+    let mut generator = Generator::new(Language::Rust);
+    generator.structure(name, struct_type)?;
+    Ok(generator.output)
+}
+
+pub fn rust_extern(name: &str, desc: &str) -> Result<String, Error> {
+    rust_extern_from_desc(name, &FuncDesc::from_str(desc)?)
+}
+
+pub(crate) fn rust_extern_from_desc(name: &str, desc: &FuncDesc) -> Result<String, Error> {
+    let mut generator = Generator::new(Language::Rust);
+    generator.function(name, desc)?;
+    Ok(generator.output)
+}
+
+impl Generator {
+    fn new(language: Language) -> Self {
+        Self { language, structs: Vec::new(), output: String::new() }
+    }
+
+    fn function(&mut self, name: &str, desc: &FuncDesc) -> Result<(), Error> {
+        let return_type = self.type_name(desc.return_type(), &format!("{}_ret", name))?;
+        let mut arguments = Vec::with_capacity(desc.argument_types().len());
+        for (i, argument_type) in desc.argument_types().iter().enumerate() {
+            arguments.push(self.type_name(argument_type, &format!("{}_arg{}", name, i))?);
+        }
+        let output = &mut self.output;
+        match self.language {
+            Language::C => {
+                let arguments = if arguments.is_empty() { "void".into() } else { arguments.join(", ") };
+                let _ = writeln!(output, "{} {}({});", return_type, name, arguments);
+            }
+            Language::Rust => {
+                let arguments = arguments.iter().enumerate().map(|(i, it)| format!("a{}: {}", i, it)).collect::<Vec<_>>();
+                let return_type = if desc.return_type() == &FuncType::Void { String::new() } else { format!(" -> {}", return_type) };
+                let _ = writeln!(output, "unsafe extern \"C\" {{\n    pub fn {}({}){};\n}}", name, arguments.join(", "), return_type);
+            }
+        }
+        Ok(())
+    }
+
+    fn structure(&mut self, name: &str, struct_type: &StructType) -> Result<String, Error> {
+        if let Some((name, _)) = self.structs.iter().find(|(_, it)| it == struct_type) {
+            return Ok(self.struct_name(name))
+        }
+        // Layouts that a compiler wouldn't produce on its own get explicit padding and packing
+        let natural = struct_type.is_natural();
+        let mut fields = Vec::with_capacity(struct_type.fields().len());
+        let mut end = 0;
+        for (i, (field, offset)) in struct_type.fields().iter().enumerate() {
+            if !natural && *offset > end { fields.push((format!("_pad{}", i), None, offset - end)); }
+            fields.push((format!("f{}", i), Some(self.type_name(field, &format!("{}_{}", name, i))?), 0));
+            end = offset + field.size();
+        }
+        if !natural && struct_type.size() > end { fields.push(("_pad".into(), None, struct_type.size() - end)); }
+        let output = &mut self.output;
+        match self.language {
+            Language::C => {
+                let _ = writeln!(output, "struct {} {{", name);
+                for (field_name, field, size) in fields.iter() {
+                    match field {
+                        Some(field) => { let _ = writeln!(output, "    {} {};", field, field_name); }
+                        None => { let _ = writeln!(output, "    uint8_t {}[{}];", field_name, size); }
+                    }
+                }
+                if natural {
+                    let _ = writeln!(output, "}};\n");
+                } else {
+                    let _ = writeln!(output, "}} __attribute__((packed, aligned({})));\n", struct_type.align());
+                }
+            }
+            Language::Rust => {
+                let repr = if natural { "C".into() } else { format!("C, packed({})", struct_type.align()) };
+                let _ = writeln!(output, "#[repr({})]\n#[allow(non_camel_case_types)]\n#[derive(Debug, Clone, Copy)]\npub struct {} {{", repr, name);
+                for (field_name, field, size) in fields.iter() {
+                    match field {
+                        Some(field) => { let _ = writeln!(output, "    pub {}: {},", field_name, field); }
+                        None => { let _ = writeln!(output, "    pub {}: [u8; {}],", field_name, size); }
+                    }
+                }
+                let _ = writeln!(output, "}}\n");
+            }
+        }
+        self.structs.push((name.into(), struct_type.clone()));
+        Ok(self.struct_name(name))
+    }
+
+    fn struct_name(&self, name: &str) -> String {
+        match self.language {
+            Language::C => format!("struct {}", name),
+            Language::Rust => name.into(),
+        }
+    }
+
+    fn type_name(&mut self, r#type: &FuncType, name: &str) -> Result<String, Error> {
+        if let FuncType::Struct(structure) = r#type {
+            return self.structure(name, structure)
+        }
+        if let FuncType::Enum(enumeration) = r#type {
+            return self.type_name(enumeration.repr(), name)
+        }
+        if let FuncType::Flags(flags) = r#type {
+            return self.type_name(flags.repr(), name)
+        }
+        let type_name = match self.language {
+            Language::C => Self::c_type_name(r#type),
+            Language::Rust => Self::rust_type_name(r#type),
+        };
+        match type_name {
+            Some(type_name) => Ok(type_name.into()),
+            None => Error::invalid_desc_from_string(format!("Type '{}' has no equivalent in generated code", r#type)),
+        }
+    }
+
+    fn c_type_name(r#type: &FuncType) -> Option<&'static str> {
+        Some(
+            match r#type {
+                FuncType::Auto              => return None,
+                FuncType::Void              => "void",
+
+                FuncType::Int               => "int",
+                FuncType::Float             => "float",
+                FuncType::Double            => "double",
+                FuncType::LongDouble        => "long double",
+                FuncType::ISize             => "intptr_t",
+                FuncType::USize             => "uintptr_t",
+
+                FuncType::S8                => "int8_t",
+                FuncType::S16               => "int16_t",
+                FuncType::S32               => "int32_t",
+                FuncType::S64               => "int64_t",
+                FuncType::S128              => "__int128",
+
+                FuncType::U8                => "uint8_t",
+                FuncType::U16               => "uint16_t",
+                FuncType::U32               => "uint32_t",
+                FuncType::U64               => "uint64_t",
+                FuncType::U128              => "unsigned __int128",
+
+                FuncType::F32               => "float",
+                FuncType::F64               => "double",
+                FuncType::F128              => "long double",
+
+                FuncType::ComplexF32        => "float _Complex",
+                FuncType::ComplexF64        => "double _Complex",
+                FuncType::ComplexLongDouble => "long double _Complex",
+
+                FuncType::Pointer           => "void *",
+                FuncType::RefStringPtr      => "const char *",
+                FuncType::BorrowStringPtr   => "char *",
+                FuncType::RefArrayPtr       => "const void *",
+                FuncType::BorrowArrayPtr    => "void *",
+
+                FuncType::Struct(_) |
+                FuncType::Enum(_) |
+                FuncType::Flags(_)          => unreachable!()
+            }
+        )
+    }
+
+    fn rust_type_name(r#type: &FuncType) -> Option<&'static str> {
+        Some(
+            match r#type {
+                FuncType::Auto              => return None,
+                FuncType::Void              => "()",
+
+                FuncType::Int               => "std::ffi::c_int",
+                FuncType::Float             => "std::ffi::c_float",
+                FuncType::Double            => "std::ffi::c_double",
+                FuncType::LongDouble        => return None,
+                FuncType::ISize             => "isize",
+                FuncType::USize             => "usize",
+
+                FuncType::S8                => "i8",
+                FuncType::S16               => "i16",
+                FuncType::S32               => "i32",
+                FuncType::S64               => "i64",
+                FuncType::S128              => "i128",
+
+                FuncType::U8                => "u8",
+                FuncType::U16               => "u16",
+                FuncType::U32               => "u32",
+                FuncType::U64               => "u64",
+                FuncType::U128              => "u128",
+
+                FuncType::F32               => "f32",
+                FuncType::F64               => "f64",
+                FuncType::F128              => return None,
+
+                FuncType::ComplexF32 |
+                FuncType::ComplexF64 |
+                FuncType::ComplexLongDouble => return None,
+
+                FuncType::Pointer           => "*mut std::ffi::c_void",
+                FuncType::RefStringPtr      => "*const std::ffi::c_char",
+                FuncType::BorrowStringPtr   => "*mut std::ffi::c_char",
+                FuncType::RefArrayPtr       => "*const std::ffi::c_void",
+                FuncType::BorrowArrayPtr    => "*mut std::ffi::c_void",
+
+                FuncType::Struct(_) |
+                FuncType::Enum(_) |
+                FuncType::Flags(_)          => unreachable!()
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c() {
+        assert_eq!(c_prototype("add", "(i32,i32)i32").unwrap(), "int32_t add(int32_t, int32_t);\n");
+        assert_eq!(c_prototype("getpid", "()int").unwrap(), "int getpid(void);\n");
+        assert_eq!(
+            c_prototype("sadd", "([i8,[f64]],&str)void").unwrap(),
+            "struct sadd_arg0_1 {\n    double f0;\n};\n\nstruct sadd_arg0 {\n    int8_t f0;\n    struct sadd_arg0_1 f1;\n};\n\nvoid sadd(struct sadd_arg0, const char *);\n"
+        );
+    }
+
+    #[test]
+    fn test_rust() {
+        assert_eq!(rust_extern("add", "(i32,i32)i32").unwrap(), "unsafe extern \"C\" {\n    pub fn add(a0: i32, a1: i32) -> i32;\n}\n");
+        assert_eq!(rust_extern("puts", "(&str)void").unwrap(), "unsafe extern \"C\" {\n    pub fn puts(a0: *const std::ffi::c_char);\n}\n");
+        assert_eq!(
+            rust_struct("Data", &StructType::from_str("[i8,[f64],u16]").unwrap()).unwrap(),
+            "#[repr(C)]\n#[allow(non_camel_case_types)]\n#[derive(Debug, Clone, Copy)]\npub struct Data_1 {\n    pub f0: f64,\n}\n\n#[repr(C)]\n#[allow(non_camel_case_types)]\n#[derive(Debug, Clone, Copy)]\npub struct Data {\n    pub f0: i8,\n    pub f1: Data_1,\n    pub f2: u16,\n}\n\n"
+        );
+        assert!(rust_extern("fabsl", "(longdouble)longdouble").is_err());
+    }
+
+    #[test]
+    fn test_packed() {
+        let struct_type = StructType::from_pystruct("b7xi4x").unwrap();
+        assert_eq!(
+            rust_struct("Packed", &struct_type).unwrap(),
+            "#[repr(C, packed(4))]\n#[allow(non_camel_case_types)]\n#[derive(Debug, Clone, Copy)]\npub struct Packed {\n    pub f0: i8,\n    pub _pad1: [u8; 7],\n    pub f1: std::ffi::c_int,\n    pub _pad: [u8; 4],\n}\n\n"
+        );
+        assert_eq!(
+            c_struct("packed", &struct_type).unwrap(),
+            "struct packed {\n    int8_t f0;\n    uint8_t _pad1[7];\n    int f1;\n    uint8_t _pad[4];\n} __attribute__((packed, aligned(4)));\n\n"
+        );
+
+        let struct_types = [struct_type, StructType::from_pystruct("<bqh").unwrap(), StructType::from_pystruct("@bq").unwrap()];
+        check_rust_layouts(&struct_types);
+        check_c_layouts(&struct_types);
+    }
+
+    fn descriptors() -> Vec<StructType> {
+        ["[i8,[f64],u16]", "[u8,[i16,[u8]],f32,usize]", "[i8,i64,i16]", "[u16,[u8,u32],*,u8]", "[f32,[i8],[i8,i16]]"]
+            .iter().map(|it| StructType::from_str(it).unwrap()).collect()
+    }
+
+    // Sizes, alignments and field offsets as the struct types report them, one line per struct
+    fn layouts(struct_types: &[StructType]) -> String {
+        struct_types.iter().map(|struct_type| {
+            let mut layout = vec![struct_type.size() as usize, struct_type.align() as usize];
+            layout.extend((0..struct_type.fields().len()).map(|it| struct_type.offset(it).unwrap() as usize));
+            format!("{:?}\n", layout)
+        }).collect()
+    }
+
+    fn compile_and_run(file_name: &str, source: &str, compiler: &str, arguments: &[&str]) -> String {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let directory = std::env::temp_dir().join(format!("sffi-codegen-{}-{}", std::process::id(), count));
+        std::fs::create_dir_all(&directory).unwrap();
+        let (file, program) = (directory.join(file_name), directory.join("layout"));
+        std::fs::write(&file, source).unwrap();
+        let status = std::process::Command::new(compiler).args(arguments).arg(&file).arg("-o").arg(&program).status().unwrap();
+        assert!(status.success(), "{}", source);
+        let output = std::process::Command::new(&program).output().unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn check_rust_layouts(struct_types: &[StructType]) {
+        // The generated code is compiled by rustc, its layouts must be the ones of the struct types
+        let mut source = String::from("use std::mem::offset_of;\n\n");
+        source.push_str(&rust_extern("sadd", "([i8,[f64]],&str)void").unwrap());
+        source.push_str(&rust_extern("add", "(i32,i32)i32").unwrap());
+        let mut main = String::from("fn main() {\n");
+        for (i, struct_type) in struct_types.iter().enumerate() {
+            source.push_str(&rust_struct(&format!("S{}", i), struct_type).unwrap());
+            let offsets = (0..struct_type.fields().len()).map(|it| format!(", offset_of!(S{}, f{})", i, it)).collect::<String>();
+            main.push_str(&format!("    println!(\"{{:?}}\", [size_of::<S{0}>(), align_of::<S{0}>(){1}]);\n", i, offsets));
+        }
+        source.push_str(&main);
+        source.push_str("}\n");
+
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+        assert_eq!(compile_and_run("layout.rs", &source, &rustc, &["--edition", "2024", "-A", "warnings"]), layouts(struct_types));
+    }
+
+    fn check_c_layouts(struct_types: &[StructType]) {
+        // Same for the C declarations, which go through the system C compiler
+        let mut source = String::from("#include <stddef.h>\n#include <stdint.h>\n#include <stdio.h>\n\n");
+        source.push_str(&c_prototype("sadd", "([i8,[f64]],&str)void").unwrap());
+        source.push_str(&c_prototype("add", "(i32,i32)i32").unwrap());
+        source.push('\n');
+        let mut main = String::from("int main(void) {\n");
+        for (i, struct_type) in struct_types.iter().enumerate() {
+            source.push_str(&c_struct(&format!("s{}", i), struct_type).unwrap());
+            let count = struct_type.fields().len() + 2;
+            let format = vec!["%zu"; count].join(", ");
+            let offsets = (0..struct_type.fields().len()).map(|it| format!(", offsetof(struct s{}, f{})", i, it)).collect::<String>();
+            main.push_str(&format!("    printf(\"[{1}]\\n\", sizeof(struct s{0}), _Alignof(struct s{0}){2});\n", i, format, offsets));
+        }
+        source.push_str(&main);
+        source.push_str("    return 0;\n}\n");
+
+        let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+        assert_eq!(compile_and_run("layout.c", &source, &cc, &["-std=gnu11", "-w"]), layouts(struct_types));
+    }
+
+    #[test]
+    fn test_rust_layout() {
+        check_rust_layouts(&descriptors());
+    }
+
+    #[test]
+    fn test_c_layout() {
+        check_c_layouts(&descriptors());
+    }
+}
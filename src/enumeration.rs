@@ -0,0 +1,256 @@
+use crate::error::Error;
+use crate::interface::FuncType;
+use std::ffi::{c_int, c_uint, c_void, CStr, CString};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ptr::{read, write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumType {
+    name: String,
+    repr: Box<FuncType>,
+    constants: Box<[(CString, i128)]>,
+}
+
+impl EnumType {
+    pub fn from_str(str: &str) -> Result<Self, Error> {
+        let (tail, value) = Self::_from_str(str.trim()).map_err(|it| it.with_descriptor(str))?;
+        if !tail.is_empty() { return Error::invalid_desc_from_string(format!("Unexpected descriptor tail: {}", tail)).map_err(|it| it.with_descriptor(str)) }
+        Ok(value)
+    }
+
+    pub(crate) fn _from_str(str: &str) -> Result<(&str, Self), Error> {
+        let (str, Declaration { name, repr, constants }) = parse_declaration("enum", str)?;
+        Ok((str, Self { name, repr: Box::new(repr), constants }))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn repr(&self) -> &FuncType {
+        &self.repr
+    }
+
+    pub fn value_of(&self, name: &str) -> Option<i128> {
+        self.constants.iter().find(|(it, _)| it.to_bytes() == name.as_bytes()).map(|(_, value)| *value)
+    }
+
+    pub fn name_of(&self, value: i128) -> Option<&str> {
+        self.name_of_c(value).and_then(|it| it.to_str().ok())
+    }
+
+    pub(crate) fn name_of_c(&self, value: i128) -> Option<&CStr> {
+        self.constants.iter().find(|(_, it)| *it == value).map(|(name, _)| name.as_c_str())
+    }
+
+    pub fn contains(&self, value: i128) -> bool {
+        self.constants.iter().any(|(_, it)| *it == value)
+    }
+
+    pub fn size(&self) -> c_uint {
+        self.repr.size()
+    }
+
+    pub fn align(&self) -> c_uint {
+        self.repr.align()
+    }
+}
+
+impl Display for EnumType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "enum {}:{}{{", self.name, self.repr)?;
+        for (i, (name, value)) in self.constants.iter().enumerate() {
+            if i > 0 { write!(f, ",")?; }
+            write!(f, "{}={}", name.to_string_lossy(), value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+pub(crate) struct Declaration {
+    pub name: String,
+    pub repr: FuncType,
+    pub constants: Box<[(CString, i128)]>,
+}
+
+pub(crate) fn parse_declaration<'a>(keyword: &str, str: &'a str) -> Result<(&'a str, Declaration), Error> {
+    let str = if let Some(str) = str.strip_prefix(keyword) && starts_with_whitespace(str) { str.trim_start() } else { return Error::invalid_desc_from_string(format!("Expected '{}' declaration", keyword)) };
+    let start = if let Some(start) = str.find('{') { start } else { return Error::invalid_desc_from_string(format!("Declaration of '{}' without body", keyword)) };
+    let end = if let Some(end) = str.find('}') { end } else { return Error::invalid_desc_from_string(format!("Declaration of '{}' without end", keyword)) };
+    if end < start { return Error::invalid_desc_from_string(format!("Invalid declaration of '{}'", keyword)) }
+
+    let (name, repr) = match str[..start].split_once(':') {
+        Some((name, repr)) => (name.trim(), repr.trim()),
+        None => (str[..start].trim(), "int"),
+    };
+    if !is_identifier(name) { return Error::invalid_desc_from_string(format!("Invalid name: {}", name)) }
+    let repr = match FuncType::_from_str(repr) {
+        Some(repr) if repr.is_integer() => repr,
+        _ => return Error::invalid_desc_from_string(format!("Invalid underlying type: {}", repr)),
+    };
+
+    let mut constants: Vec<(CString, i128)> = vec![];
+    let mut next = 0i128;
+    for constant in str[start + 1..end].split(',').map(str::trim) {
+        if constant.is_empty() { continue }
+        let (constant, value) = match constant.split_once('=') {
+            Some((constant, value)) => (constant.trim(), parse_integer(value.trim())?),
+            None => (constant, next),
+        };
+        if !is_identifier(constant) { return Error::invalid_desc_from_string(format!("Invalid name: {}", constant)) }
+        if constants.iter().any(|(it, _)| it.to_bytes() == constant.as_bytes()) { return Error::invalid_desc_from_string(format!("Duplicate name: {}", constant)) }
+        if !fits(&repr, value) { return Error::invalid_desc_from_string(format!("Value of '{}' doesn't fit into '{}'", constant, repr)) }
+        constants.push((CString::new(constant).map_err(Error::RustStringToCString)?, value));
+        next = value + 1;
+    }
+    Ok((&str[end + 1..], Declaration { name: name.into(), repr, constants: constants.into_boxed_slice() }))
+}
+
+fn starts_with_whitespace(str: &str) -> bool {
+    str.chars().next().is_some_and(char::is_whitespace)
+}
+
+fn is_identifier(str: &str) -> bool {
+    let mut chars = str.chars();
+    chars.next().is_some_and(|it| it.is_ascii_alphabetic() || it == '_') && chars.all(|it| it.is_ascii_alphanumeric() || it == '_')
+}
+
+pub(crate) fn parse_integer(str: &str) -> Result<i128, Error> {
+    let (negative, digits) = match str.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, str),
+    };
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => digits.parse::<i128>(),
+    };
+    match value {
+        Ok(value) => Ok(if negative { -value } else { value }),
+        Err(_) => Error::invalid_desc_from_string(format!("Invalid value: {}", str)),
+    }
+}
+
+pub(crate) fn fits(r#type: &FuncType, value: i128) -> bool {
+    let mut buffer = 0u128;
+    unsafe {
+        write_integer(r#type, &mut buffer as *mut u128 as *mut c_void, value);
+        read_integer(r#type, &buffer as *const u128 as *const c_void) == Some(value)
+    }
+}
+
+pub(crate) unsafe fn read_integer(r#type: &FuncType, from_addr: *const c_void) -> Option<i128> {
+    unsafe {
+        Some(
+            match r#type {
+                FuncType::Int   => read::<c_int>    (from_addr as *const _) as _,
+                FuncType::ISize => read::<isize>    (from_addr as *const _) as _,
+                FuncType::USize => read::<usize>    (from_addr as *const _) as _,
+
+                FuncType::S8    => read::<i8>       (from_addr as *const _) as _,
+                FuncType::S16   => read::<i16>      (from_addr as *const _) as _,
+                FuncType::S32   => read::<i32>      (from_addr as *const _) as _,
+                FuncType::S64   => read::<i64>      (from_addr as *const _) as _,
+
+                FuncType::U8    => read::<u8>       (from_addr as *const _) as _,
+                FuncType::U16   => read::<u16>      (from_addr as *const _) as _,
+                FuncType::U32   => read::<u32>      (from_addr as *const _) as _,
+                FuncType::U64   => read::<u64>      (from_addr as *const _) as _,
+
+                FuncType::Enum(it) => return read_integer(it.repr(), from_addr),
+                FuncType::Flags(it) => return read_integer(it.repr(), from_addr),
+                _ => return None
+            }
+        )
+    }
+}
+
+pub(crate) unsafe fn write_integer(r#type: &FuncType, into_addr: *mut c_void, value: i128) {
+    unsafe {
+        match r#type {
+            FuncType::Int   => write::<c_int>   (into_addr as *mut _, value as _),
+            FuncType::ISize => write::<isize>   (into_addr as *mut _, value as _),
+            FuncType::USize => write::<usize>   (into_addr as *mut _, value as _),
+
+            FuncType::S8    => write::<i8>      (into_addr as *mut _, value as _),
+            FuncType::S16   => write::<i16>     (into_addr as *mut _, value as _),
+            FuncType::S32   => write::<i32>     (into_addr as *mut _, value as _),
+            FuncType::S64   => write::<i64>     (into_addr as *mut _, value as _),
+
+            FuncType::U8    => write::<u8>      (into_addr as *mut _, value as _),
+            FuncType::U16   => write::<u16>     (into_addr as *mut _, value as _),
+            FuncType::U32   => write::<u32>     (into_addr as *mut _, value as _),
+            FuncType::U64   => write::<u64>     (into_addr as *mut _, value as _),
+
+            FuncType::Enum(it) => write_integer(it.repr(), into_addr, value),
+            FuncType::Flags(it) => write_integer(it.repr(), into_addr, value),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Adapter;
+    use crate::structure::StructType;
+    use std::ffi::c_char;
+    use std::ptr::null;
+
+    #[test]
+    fn test_parse() {
+        let enum_type = EnumType::from_str("enum Color:u32{Red=0,Green=1,Blue=4}").unwrap();
+        assert_eq!(enum_type.name(), "Color");
+        assert_eq!(enum_type.repr(), &FuncType::U32);
+        assert_eq!(enum_type.value_of("Blue"), Some(4));
+        assert_eq!(enum_type.name_of(1), Some("Green"));
+        assert_eq!(enum_type.name_of(2), None);
+        assert_eq!(enum_type.to_string(), "enum Color:u32{Red=0,Green=1,Blue=4}");
+    }
+
+    #[test]
+    fn test_implicit() {
+        let enum_type = EnumType::from_str("enum Level { Low = -1, Mid, High = 0x10 }").unwrap();
+        assert_eq!(enum_type.repr(), &FuncType::Int);
+        assert_eq!(enum_type.value_of("Mid"), Some(0));
+        assert_eq!(enum_type.value_of("High"), Some(16));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(EnumType::from_str("enum Color:f32{Red}").is_err());
+        assert!(EnumType::from_str("enum Color:u8{Red=256}").is_err());
+        assert!(EnumType::from_str("enum Color:u8{Red=-1}").is_err());
+        assert!(EnumType::from_str("enum Color{Red,Red}").is_err());
+        assert!(EnumType::from_str("enum 1Color{Red}").is_err());
+        assert!(EnumType::from_str("enum Color{Red").is_err());
+        assert!(EnumType::from_str("enumColor{Red}").is_err());
+    }
+
+    #[test]
+    fn test_adapter() {
+        let struct_type = StructType::from_str("[enum Color:u16{Red=0,Green=1,Blue=4}]").unwrap();
+        let mut structure = 0u64;
+        let structure = &mut structure as *mut u64 as *mut c_void;
+        let mut adapter = Adapter::from_str("[i32]").unwrap();
+        adapter.set(&struct_type, structure, 0, &4i32 as *const i32 as *const c_void).unwrap();
+        assert!(adapter.set(&struct_type, structure, 0, &3i32 as *const i32 as *const c_void).is_err());
+        let mut result = 0i32;
+        adapter.get(&struct_type, structure, 0, &mut result as *mut i32 as *mut c_void).unwrap();
+        assert_eq!(result, 4);
+
+        adapter.set_strict(false);
+        adapter.set(&struct_type, structure, 0, &3i32 as *const i32 as *const c_void).unwrap();
+        adapter.get(&struct_type, structure, 0, &mut result as *mut i32 as *mut c_void).unwrap();
+        assert_eq!(result, 3);
+
+        let adapter = Adapter::from_str("[&str]").unwrap();
+        let name = c"Green".as_ptr();
+        adapter.set(&struct_type, structure, 0, &name as *const *const c_char as *const c_void).unwrap();
+        let mut name = null::<c_char>();
+        adapter.get(&struct_type, structure, 0, &mut name as *mut *const c_char as *mut c_void).unwrap();
+        assert_eq!(unsafe { CStr::from_ptr(name) }, c"Green");
+        let name = c"Yellow".as_ptr();
+        assert!(adapter.set(&struct_type, structure, 0, &name as *const *const c_char as *const c_void).is_err());
+    }
+}
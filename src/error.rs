@@ -1,66 +1,250 @@
+use crate::interface::FuncType;
 use crate::internal::try_c_const_char_to_string;
-use std::ffi::c_char;
+use std::ffi::{c_char, NulError};
+use std::fmt;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug)]
 pub enum Error {
-    RustStringToCString,
+    RustStringToCString(NulError),
     CStringToRustString,
-    LibraryOpen(Option<String>),
-    LibrarySymbol(Option<String>),
-    LibraryClose(Option<String>),
+    LibraryOpen { path: Option<String>, message: Option<String> },
+    LibraryNotFound { path: Option<String>, message: Option<String> },
+    LibraryWrongArch { path: Option<String>, message: Option<String> },
+    LibraryMissingDependency { path: Option<String>, dependency: Option<String>, message: Option<String> },
+    LibraryUndefinedSymbol { path: Option<String>, symbol: Option<String>, message: Option<String> },
+    LibraryPermissionDenied { path: Option<String>, message: Option<String> },
+    LibrarySymbol { symbol: Option<String>, message: Option<String> },
+    LibraryClose { message: Option<String> },
     FFIBadTypeDef,
     FFIBadABI,
     FFIBadArgType,
-    InvalidDescriptor(Option<String>),
-    InvalidCast(Option<String>),
-    InvalidArguments(Option<String>),
+    InvalidDescriptor { descriptor: Option<String>, message: Option<String> },
+    InvalidCast { from: Option<String>, into: Option<String>, message: Option<String> },
+    InvalidArguments { index: Option<usize>, message: Option<String> },
+    InvalidHandle { message: Option<String> },
+    Allocation { size: usize, align: usize },
+    Panic { message: Option<String> },
+    Function { symbol: String, source: Box<Error> },
+    Argument { index: usize, source: Box<Error> },
 }
 
-
 impl Error {
     pub fn get_message(&self) -> Option<&str> {
         match self {
-            Error::RustStringToCString |
+            Error::RustStringToCString(_) |
             Error::CStringToRustString |
             Error::FFIBadTypeDef |
             Error::FFIBadABI |
-            Error::FFIBadArgType
+            Error::FFIBadArgType |
+            Error::Allocation { .. }
             => None,
-            Error::LibraryOpen(str) |
-            Error::LibrarySymbol(str) |
-            Error::LibraryClose(str) |
-            Error::InvalidDescriptor(str) |
-            Error::InvalidCast(str) |
-            Error::InvalidArguments(str)
-            => if let Some(str) = str { Some(&str) } else { None },
+            Error::LibraryOpen { message, .. } |
+            Error::LibraryNotFound { message, .. } |
+            Error::LibraryWrongArch { message, .. } |
+            Error::LibraryMissingDependency { message, .. } |
+            Error::LibraryUndefinedSymbol { message, .. } |
+            Error::LibraryPermissionDenied { message, .. } |
+            Error::LibrarySymbol { message, .. } |
+            Error::LibraryClose { message } |
+            Error::InvalidDescriptor { message, .. } |
+            Error::InvalidCast { message, .. } |
+            Error::InvalidArguments { message, .. } |
+            Error::InvalidHandle { message } |
+            Error::Panic { message }
+            => message.as_deref(),
+            Error::Function { source, .. } |
+            Error::Argument { source, .. }
+            => source.get_message(),
         }
     }
 
-    pub(crate) fn lib_open_from_cstr<T>(str: *const c_char) -> Result<T, Error> {
-        Err(Error::LibraryOpen(try_c_const_char_to_string(str)))
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Function { source, .. } |
+            Error::Argument { source, .. }
+            => source.root(),
+            _ => self,
+        }
     }
 
-    pub(crate) fn lib_symbol_from_cstr<T>(str: *const c_char) -> Result<T, Error> {
-        Err(Error::LibrarySymbol(try_c_const_char_to_string(str)))
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            report.push_str(": ");
+            report.push_str(&error.to_string());
+            source = error.source();
+        }
+        report
+    }
+
+    pub(crate) fn with_descriptor(self, str: &str) -> Self {
+        match self {
+            Error::InvalidDescriptor { message, .. } => Error::InvalidDescriptor { descriptor: Some(str.trim().into()), message },
+            error => error,
+        }
+    }
+
+    pub(crate) fn with_function(self, symbol: *const c_char) -> Self {
+        Error::Function { symbol: try_c_const_char_to_string(symbol).unwrap_or_default(), source: Box::new(self) }
+    }
+
+    pub(crate) fn with_argument(self, index: usize) -> Self {
+        Error::Argument { index, source: Box::new(self) }
+    }
+
+    pub(crate) fn lib_symbol_from_cstr<T>(symbol: *const c_char, str: *const c_char) -> Result<T, Error> {
+        Err(Error::LibrarySymbol { symbol: optional_c_const_char(symbol), message: optional_c_const_char(str) })
     }
 
     pub(crate) fn lib_close_from_cstr<T>(str: *const c_char) -> Result<T, Error> {
-        Err(Error::LibraryClose(try_c_const_char_to_string(str)))
+        Err(Error::LibraryClose { message: optional_c_const_char(str) })
     }
 
     pub(crate) fn invalid_desc_from_str<T>(str: &str) -> Result<T, Error> {
-        Err(Error::InvalidDescriptor(Some(str.into())))
+        Err(Error::InvalidDescriptor { descriptor: None, message: Some(str.into()) })
     }
 
     pub(crate) fn invalid_desc_from_string<T>(str: String) -> Result<T, Error> {
-        Err(Error::InvalidDescriptor(Some(str)))
+        Err(Error::InvalidDescriptor { descriptor: None, message: Some(str) })
+    }
+
+    pub(crate) fn invalid_cast<T>(from_type: &FuncType, into_type: &FuncType) -> Result<T, Error> {
+        Err(Error::InvalidCast { from: Some(from_type.to_string()), into: Some(into_type.to_string()), message: None })
     }
 
-    pub(crate) fn invalid_cast_from_string<T>(str: String) -> Result<T, Error> {
-        Err(Error::InvalidCast(Some(str)))
+    pub(crate) fn invalid_cast_from_string<T>(from_type: &FuncType, into_type: &FuncType, str: String) -> Result<T, Error> {
+        Err(Error::InvalidCast { from: Some(from_type.to_string()), into: Some(into_type.to_string()), message: Some(str) })
     }
 
     pub(crate) fn invalid_args_from_string<T>(str: String) -> Result<T, Error> {
-        Err(Error::InvalidArguments(Some(str)))
+        Err(Error::InvalidArguments { index: None, message: Some(str) })
     }
-}
\ No newline at end of file
+
+    pub(crate) fn invalid_arg_from_string<T>(index: usize, str: String) -> Result<T, Error> {
+        Err(Error::InvalidArguments { index: Some(index), message: Some(str) })
+    }
+}
+
+pub(crate) fn optional_c_const_char(str: *const c_char) -> Option<String> {
+    if str.is_null() { None } else { try_c_const_char_to_string(str) }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::RustStringToCString(_) => write!(f, "Can't convert Rust string into C string"),
+            Error::CStringToRustString => write!(f, "Can't convert C string into Rust string"),
+            Error::LibraryOpen { path, .. } => match path {
+                Some(path) => write!(f, "Can't open library '{}'", path),
+                None => write!(f, "Can't open library"),
+            },
+            Error::LibraryNotFound { path, .. } => match path {
+                Some(path) => write!(f, "Can't find library '{}'", path),
+                None => write!(f, "Can't find library"),
+            },
+            Error::LibraryWrongArch { path, .. } => match path {
+                Some(path) => write!(f, "Library '{}' is built for another architecture", path),
+                None => write!(f, "Library is built for another architecture"),
+            },
+            Error::LibraryMissingDependency { dependency, .. } => match dependency {
+                Some(dependency) => write!(f, "Can't find dependency '{}'", dependency),
+                None => write!(f, "Can't find dependency"),
+            },
+            Error::LibraryUndefinedSymbol { symbol, .. } => match symbol {
+                Some(symbol) => write!(f, "Can't bind undefined symbol '{}'", symbol),
+                None => write!(f, "Can't bind undefined symbol"),
+            },
+            Error::LibraryPermissionDenied { path, .. } => match path {
+                Some(path) => write!(f, "Permission denied to open library '{}'", path),
+                None => write!(f, "Permission denied to open library"),
+            },
+            Error::LibrarySymbol { symbol, .. } => match symbol {
+                Some(symbol) => write!(f, "Can't find symbol '{}'", symbol),
+                None => write!(f, "Can't find symbol"),
+            },
+            Error::LibraryClose { .. } => write!(f, "Can't close library"),
+            Error::FFIBadTypeDef => write!(f, "Bad type definition"),
+            Error::FFIBadABI => write!(f, "Bad ABI"),
+            Error::FFIBadArgType => write!(f, "Bad argument type"),
+            Error::InvalidDescriptor { descriptor, .. } => match descriptor {
+                Some(descriptor) => write!(f, "Invalid descriptor '{}'", descriptor),
+                None => write!(f, "Invalid descriptor"),
+            },
+            Error::InvalidCast { from, into, .. } => match (from, into) {
+                (Some(from), Some(into)) => write!(f, "Invalid cast from '{}' into '{}'", from, into),
+                _ => write!(f, "Invalid cast"),
+            },
+            Error::InvalidArguments { index, .. } => match index {
+                Some(index) => write!(f, "Invalid argument {}", index),
+                None => write!(f, "Invalid arguments"),
+            },
+            Error::InvalidHandle { .. } => write!(f, "Invalid handle"),
+            Error::Allocation { size, align } => write!(f, "Can't allocate {} bytes aligned to {}", size, align),
+            Error::Panic { .. } => write!(f, "Panic"),
+            Error::Function { symbol, .. } => return write!(f, "Can't bind function '{}'", symbol),
+            Error::Argument { index, .. } => return write!(f, "Can't pass argument {}", index),
+        }?;
+        match self.get_message() {
+            Some(message) => write!(f, ": {}", message),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::RustStringToCString(error) => Some(error),
+            Error::Function { source, .. } |
+            Error::Argument { source, .. }
+            => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Adapter;
+    use crate::interface::FuncHandle;
+    use crate::library::LibHandle;
+    use std::ffi::c_void;
+
+    #[test]
+    fn test_display() {
+        let error = FuncType::from_str("[i32,q32]").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid descriptor: Unknown type: q32");
+        let error = Adapter::from_str("[i32,q32]").err().unwrap();
+        assert!(matches!(&error, Error::InvalidDescriptor { descriptor: Some(descriptor), .. } if descriptor == "[i32,q32]"));
+        assert_eq!(error.to_string(), "Invalid descriptor '[i32,q32]': Unknown type: q32");
+    }
+
+    #[test]
+    fn test_source() {
+        let mut lib = LibHandle::open("").unwrap();
+        let error = lib.func("abs", "(q32)int").unwrap_err();
+        assert!(matches!(error.root(), Error::InvalidDescriptor { .. }));
+        assert_eq!(error.report(), "Can't bind function 'abs': Invalid descriptor '(q32)int': Unknown type: q32");
+
+        let error = lib.func("sffi_missing_symbol", "()void").unwrap_err();
+        assert!(matches!(&error, Error::LibrarySymbol { symbol: Some(symbol), .. } if symbol == "sffi_missing_symbol"));
+
+        let error: Box<dyn std::error::Error> = Box::new(LibHandle::open("a\0b").unwrap_err());
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_argument() {
+        let mut lib = LibHandle::open("").unwrap();
+        let func = lib.func("abs", "(int)int").unwrap() as *const FuncHandle;
+        let adapter = Adapter::from_str("(*)int").unwrap();
+        let mut result = 0i32;
+        let mut argument = 0usize;
+        let error = adapter.call(func, &mut result as *mut i32 as *mut c_void, &mut [&mut argument as *mut usize as *mut c_void]).unwrap_err();
+        assert!(matches!(&error, Error::Argument { index: 0, .. }));
+        assert!(matches!(error.root(), Error::InvalidCast { from: Some(from), into: Some(into), .. } if from == "*" && into == "int"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::enumeration::{parse_declaration, parse_integer, Declaration};
+use crate::error::Error;
+use crate::interface::FuncType;
+use std::ffi::{c_uint, CString};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlagsType {
+    name: String,
+    repr: Box<FuncType>,
+    constants: Box<[(CString, i128)]>,
+}
+
+impl FlagsType {
+    pub fn from_str(str: &str) -> Result<Self, Error> {
+        let (tail, value) = Self::_from_str(str.trim()).map_err(|it| it.with_descriptor(str))?;
+        if !tail.is_empty() { return Error::invalid_desc_from_string(format!("Unexpected descriptor tail: {}", tail)).map_err(|it| it.with_descriptor(str)) }
+        Ok(value)
+    }
+
+    pub(crate) fn _from_str(str: &str) -> Result<(&str, Self), Error> {
+        let (str, Declaration { name, repr, constants }) = parse_declaration("flags", str)?;
+        Ok((str, Self { name, repr: Box::new(repr), constants }))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn repr(&self) -> &FuncType {
+        &self.repr
+    }
+
+    pub fn value_of(&self, name: &str) -> Option<i128> {
+        self.constants.iter().find(|(it, _)| it.to_bytes() == name.as_bytes()).map(|(_, value)| *value)
+    }
+
+    pub fn mask(&self) -> i128 {
+        self.constants.iter().fold(0, |mask, (_, value)| mask | value)
+    }
+
+    pub fn encode(&self, names: &[&str]) -> Result<i128, Error> {
+        let mut value = 0;
+        for name in names {
+            value |= match self.value_of(name) {
+                Some(value) => value,
+                None => return Err(Error::InvalidCast { from: None, into: None, message: Some(format!("Unknown flag of 'flags {}': {}", self.name, name)) }),
+            };
+        }
+        Ok(value)
+    }
+
+    pub fn encode_str(&self, str: &str) -> Result<i128, Error> {
+        let mut value = 0;
+        for name in str.split('|').map(str::trim) {
+            if name.is_empty() { continue }
+            value |= match self.value_of(name) {
+                Some(value) => value,
+                None if name.starts_with(|it: char| it.is_ascii_digit()) => parse_integer(name)?,
+                None => return Err(Error::InvalidCast { from: None, into: None, message: Some(format!("Unknown flag of 'flags {}': {}", self.name, name)) }),
+            };
+        }
+        Ok(value)
+    }
+
+    pub fn decode(&self, value: i128) -> (Vec<&str>, i128) {
+        let mut names = vec![];
+        let mut residual = value;
+        for (name, flag) in self.constants.iter() {
+            let name = name.to_str().unwrap_or_default();
+            if *flag == 0 {
+                if value == 0 { names.push(name); }
+            } else if value & flag == *flag {
+                names.push(name);
+                residual &= !flag;
+            }
+        }
+        (names, residual)
+    }
+
+    pub fn decode_str(&self, value: i128) -> String {
+        let (mut names, residual) = self.decode(value);
+        let residual = format!("{:#x}", residual);
+        if names.is_empty() || value & !self.mask() != 0 { names.push(&residual); }
+        names.join("|")
+    }
+
+    pub fn size(&self) -> c_uint {
+        self.repr.size()
+    }
+
+    pub fn align(&self) -> c_uint {
+        self.repr.align()
+    }
+}
+
+impl Display for FlagsType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "flags {}:{}{{", self.name, self.repr)?;
+        for (i, (name, value)) in self.constants.iter().enumerate() {
+            if i > 0 { write!(f, ",")?; }
+            write!(f, "{}={:#x}", name.to_string_lossy(), value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Adapter;
+    use crate::structure::StructType;
+    use std::ffi::{c_char, c_void, CStr};
+    use std::ptr::null_mut;
+
+    #[test]
+    fn test_parse() {
+        let flags_type = FlagsType::from_str("flags Open:u32{RDONLY=0,WRONLY=1,RDWR=2,CREAT=0x40}").unwrap();
+        assert_eq!(flags_type.name(), "Open");
+        assert_eq!(flags_type.repr(), &FuncType::U32);
+        assert_eq!(flags_type.mask(), 0x43);
+        assert_eq!(flags_type.to_string(), "flags Open:u32{RDONLY=0x0,WRONLY=0x1,RDWR=0x2,CREAT=0x40}");
+    }
+
+    #[test]
+    fn test_encode() {
+        let flags_type = FlagsType::from_str("flags Open:u32{RDONLY=0,WRONLY=1,RDWR=2,CREAT=0x40}").unwrap();
+        assert_eq!(flags_type.encode(&["WRONLY", "CREAT"]).unwrap(), 0x41);
+        assert_eq!(flags_type.encode_str("RDWR | CREAT | 0x100").unwrap(), 0x142);
+        assert!(flags_type.encode(&["TRUNC"]).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let flags_type = FlagsType::from_str("flags Open:u32{RDONLY=0,WRONLY=1,RDWR=2,CREAT=0x40}").unwrap();
+        assert_eq!(flags_type.decode(0x41), (vec!["WRONLY", "CREAT"], 0));
+        assert_eq!(flags_type.decode(0x0), (vec!["RDONLY"], 0));
+        assert_eq!(flags_type.decode(0x142), (vec!["RDWR", "CREAT"], 0x100));
+        assert_eq!(flags_type.decode_str(0x142), "RDWR|CREAT|0x100");
+        assert_eq!(flags_type.decode_str(0x40), "CREAT");
+    }
+
+    #[test]
+    fn test_adapter() {
+        let struct_type = StructType::from_str("[flags Open:u32{RDONLY=0,WRONLY=1,RDWR=2,CREAT=0x40}]").unwrap();
+        let mut structure = 0u64;
+        let structure = &mut structure as *mut u64 as *mut c_void;
+        let mut adapter = Adapter::from_str("[*str]").unwrap();
+        let flags = c"WRONLY|CREAT".as_ptr();
+        adapter.set(&struct_type, structure, 0, &flags as *const *const c_char as *const c_void).unwrap();
+        let mut flags = null_mut::<c_char>();
+        adapter.get(&struct_type, structure, 0, &mut flags as *mut *mut c_char as *mut c_void).unwrap();
+        assert_eq!(unsafe { CStr::from_ptr(flags) }, c"WRONLY|CREAT");
+        StructType::free(flags as *mut c_void);
+
+        let flags = c"CREAT|0x100".as_ptr();
+        assert!(adapter.set(&struct_type, structure, 0, &flags as *const *const c_char as *const c_void).is_err());
+        adapter.set_strict(false);
+        adapter.set(&struct_type, structure, 0, &flags as *const *const c_char as *const c_void).unwrap();
+
+        let adapter = Adapter::from_str("[u64]").unwrap();
+        let mut result = 0u64;
+        assert!(adapter.get(&struct_type, structure, 0, &mut result as *mut u64 as *mut c_void).is_err());
+        adapter.set(&struct_type, structure, 0, &0x2u64 as *const u64 as *const c_void).unwrap();
+        adapter.get(&struct_type, structure, 0, &mut result as *mut u64 as *mut c_void).unwrap();
+        assert_eq!(result, 0x2);
+    }
+}
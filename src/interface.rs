@@ -1,9 +1,14 @@
+use crate::enumeration::EnumType;
 use crate::error::Error;
+use crate::flags::FlagsType;
 use crate::internal::starts_with;
 use crate::structure::StructType;
 use libffi::high::ffi_abi_FFI_DEFAULT_ABI;
 use libffi::low::ffi_cif;
+#[cfg(not(windows))]
+use libffi::raw::{ffi_type_complex_double, ffi_type_complex_float, ffi_type_complex_longdouble};
 use libffi::raw::{ffi_call, ffi_prep_cif, ffi_raw, ffi_raw_call, ffi_status_FFI_BAD_ABI, ffi_status_FFI_BAD_ARGTYPE, ffi_status_FFI_BAD_TYPEDEF, ffi_status_FFI_OK, ffi_type, ffi_type_double, ffi_type_float, ffi_type_longdouble, ffi_type_pointer, ffi_type_sint16, ffi_type_sint32, ffi_type_sint64, ffi_type_sint8, ffi_type_uint16, ffi_type_uint32, ffi_type_uint64, ffi_type_uint8, ffi_type_void, FFI_TYPE_STRUCT};
+use std::cell::UnsafeCell;
 use std::cmp::min;
 use std::ffi::{c_double, c_float, c_int, c_uint, c_void};
 use std::fmt;
@@ -18,20 +23,43 @@ pub struct FuncHandle {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct FuncDesc {
+pub struct FuncDesc {
     argument_types: Box<[FuncType]>,
     return_type: FuncType,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum FuncType {
+pub enum FuncType {
     Auto, Void,
     Int, Float, Double, LongDouble, ISize, USize,
-    S8, S16, S32, S64, U8, U16, U32, U64, F32, F64, F128,
+    S8, S16, S32, S64, S128, U8, U16, U32, U64, U128, F32, F64, F128,
+    ComplexF32, ComplexF64, ComplexLongDouble,
     Pointer,
     RefStringPtr, BorrowStringPtr,
     RefArrayPtr, BorrowArrayPtr,
-    Struct(StructType)
+    Struct(StructType),
+    Enum(EnumType),
+    Flags(FlagsType)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    Auto, Void,
+    Int, Float, Double, LongDouble, ISize, USize,
+    S8, S16, S32, S64, S128, U8, U16, U32, U64, U128, F32, F64, F128,
+    ComplexF32, ComplexF64, ComplexLongDouble,
+    Pointer,
+    RefStringPtr, BorrowStringPtr,
+    RefArrayPtr, BorrowArrayPtr,
+    Struct,
+    Enum,
+    Flags
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Sffi, Jni, Dyncall
 }
 
 struct FuncDescHelper {
@@ -40,34 +68,20 @@ struct FuncDescHelper {
     arguments_types: Box<[*mut ffi_type]>,
 }
 
-impl FuncHandle {
-    pub(crate) fn new(symbol: *const c_void, desc: &str) -> Result<Self, Error> {
-        let desc = FuncDesc::from_str(desc)?;
+// A prepared `ffi_cif`, it points into the helper's heap allocations so both are kept together
+pub(crate) struct FuncCif {
+    cif: UnsafeCell<ffi_cif>,
+    _helper: FuncDescHelper,
+}
 
+impl FuncHandle {
+    pub(crate) fn new(symbol: *const c_void, desc: FuncDesc) -> Result<Self, Error> {
         // Verify
-        let mut helper = FuncDescHelper::new(&desc.return_type, &desc.argument_types)?;
-        let mut cif = ffi_cif::default();
-        #[allow(nonstandard_style)]
-        match unsafe {
-            ffi_prep_cif(
-                &mut cif,
-                ffi_abi_FFI_DEFAULT_ABI,
-                helper.arguments_types.len() as c_uint,
-                helper.return_type,
-                helper.arguments_types.as_mut_ptr(),
-            )
-        } {
-            ffi_status_FFI_OK => {},
-            ffi_status_FFI_BAD_TYPEDEF => return Err(Error::FFIBadTypeDef),
-            ffi_status_FFI_BAD_ABI => return Err(Error::FFIBadABI),
-            ffi_status_FFI_BAD_ARGTYPE => return Err(Error::FFIBadArgType),
-            _ => unreachable!()
-        };
-
+        FuncCif::new(&desc)?;
         Ok(Self { desc, symbol })
     }
 
-    pub(crate) fn desc(&self) -> &FuncDesc {
+    pub fn desc(&self) -> &FuncDesc {
         &self.desc
     }
 
@@ -76,26 +90,7 @@ impl FuncHandle {
     }
 
     pub(crate) unsafe fn _call(&self, rvalue: *mut c_void, avalue: *mut *mut c_void) {
-        unsafe {
-            let mut cif = ffi_cif::default();
-            let mut helper = FuncDescHelper::new(&self.desc.return_type, &self.desc.argument_types).unwrap_unchecked();
-
-            #[allow(nonstandard_style)]
-            ffi_prep_cif(
-                &mut cif,
-                ffi_abi_FFI_DEFAULT_ABI,
-                helper.arguments_types.len() as c_uint,
-                helper.return_type,
-                helper.arguments_types.as_mut_ptr(),
-            );
-
-            ffi_call(
-                &mut cif,
-                transmute(self.symbol),
-                rvalue,
-                avalue
-            );
-        }
+        unsafe { FuncCif::new(&self.desc).unwrap_unchecked()._call(self.symbol, rvalue, avalue) }
     }
 
     pub unsafe fn call_raw(&self, result: *mut c_void, arguments: &mut [ffi_raw]) {
@@ -104,24 +99,8 @@ impl FuncHandle {
 
     pub(crate) unsafe fn _call_raw(&self, result: *mut c_void, arguments: *mut ffi_raw) {
         unsafe {
-            let mut cif = ffi_cif::default();
-            let mut helper = FuncDescHelper::new(&self.desc.return_type, &self.desc.argument_types).unwrap_unchecked();
-
-            #[allow(nonstandard_style)]
-            ffi_prep_cif(
-                &mut cif,
-                ffi_abi_FFI_DEFAULT_ABI,
-                helper.arguments_types.len() as c_uint,
-                helper.return_type,
-                helper.arguments_types.as_mut_ptr(),
-            );
-
-            ffi_raw_call(
-                &mut cif,
-                transmute(self.symbol),
-                result,
-                arguments
-            );
+            let cif = FuncCif::new(&self.desc).unwrap_unchecked();
+            ffi_raw_call(cif.cif.get(), transmute(self.symbol), result, arguments);
         }
     }
 
@@ -131,8 +110,7 @@ impl FuncHandle {
 }
 
 impl FuncDesc {
-    #[cfg(test)]
-    pub fn new(argument_types: Box<[FuncType]>, return_type: FuncType) -> Self {
+    pub(crate) fn new(argument_types: Box<[FuncType]>, return_type: FuncType) -> Self {
         Self {
             argument_types,
             return_type,
@@ -140,6 +118,10 @@ impl FuncDesc {
     }
 
     pub fn from_str(str: &str) -> Result<FuncDesc, Error> {
+        Self::_from_str(str).map_err(|it| it.with_descriptor(str))
+    }
+
+    fn _from_str(str: &str) -> Result<FuncDesc, Error> {
         let str = str.trim();
         if !starts_with(str, '(') { return Error::invalid_desc_from_str("Invalid descriptor start") }
         let str = &str[1..];
@@ -155,6 +137,14 @@ impl FuncDesc {
         )
     }
 
+    pub fn parse_with(syntax: Syntax, str: &str) -> Result<FuncDesc, Error> {
+        match syntax {
+            Syntax::Sffi => Self::from_str(str),
+            Syntax::Jni => Self::from_jni(str).map_err(|it| it.with_descriptor(str)),
+            Syntax::Dyncall => Self::from_dyncall(str).map_err(|it| it.with_descriptor(str)),
+        }
+    }
+
     pub fn argument_types(&self) -> &'_[FuncType] {
         &self.argument_types
     }
@@ -162,6 +152,17 @@ impl FuncDesc {
     pub fn return_type(&self) -> &FuncType {
         &self.return_type
     }
+
+    pub fn descriptor(&self) -> String {
+        let mut str = String::from("(");
+        for (i, argument_type) in self.argument_types.iter().enumerate() {
+            if i > 0 { str.push(','); }
+            argument_type.write_descriptor(&mut str);
+        }
+        str.push(')');
+        self.return_type.write_descriptor(&mut str);
+        str
+    }
 }
 
 impl FuncType {
@@ -188,9 +189,16 @@ impl FuncType {
     pub(crate) fn __from_str(str: &str) -> Result<(&str, Self), Error> {
         if str.starts_with("[") {
             let (str, structure) = StructType::_from_str(str)?;
-            Ok((str, FuncType::Struct(structure)))
+            Ok((str.strip_prefix(",").unwrap_or(str), FuncType::Struct(structure)))
+        } else if str.starts_with("enum ") {
+            let (str, enumeration) = EnumType::_from_str(str)?;
+            Ok((str.strip_prefix(",").unwrap_or(str), FuncType::Enum(enumeration)))
+        } else if str.starts_with("flags ") {
+            let (str, flags) = FlagsType::_from_str(str)?;
+            Ok((str.strip_prefix(",").unwrap_or(str), FuncType::Flags(flags)))
         } else {
             let next = if let Some(next) = str.find(",") { next } else { str.len() };
+            if &str[..next] == "clongdouble" { return Error::invalid_desc_from_str("Type 'clongdouble' is not supported, long double values can't be converted") }
             let value = Self::_from_str(&str[..next]);
             let value = if let Some(value) = value { value } else { return Error::invalid_desc_from_string(format!("Unknown type: {}", &str[..next])) };
             Ok((&str[min(next + 1, str.len())..], value))
@@ -214,16 +222,21 @@ impl FuncType {
                 "i16"       => FuncType::S16,
                 "i32"       => FuncType::S32,
                 "i64"       => FuncType::S64,
+                "i128"      => FuncType::S128,
 
                 "u8"        => FuncType::U8,
                 "u16"       => FuncType::U16,
                 "u32"       => FuncType::U32,
                 "u64"       => FuncType::U64,
+                "u128"      => FuncType::U128,
 
                 "f32"       => FuncType::F32,
                 "f64"       => FuncType::F64,
                 "f128"      => FuncType::F128,
 
+                "cf32"      => FuncType::ComplexF32,
+                "cf64"      => FuncType::ComplexF64,
+
                 "*"         => FuncType::Pointer,
                 "&str"      => FuncType::RefStringPtr,
                 "*str"      => FuncType::BorrowStringPtr,
@@ -235,6 +248,75 @@ impl FuncType {
         )
     }
 
+    pub fn kind(&self) -> TypeKind {
+        match self {
+            FuncType::Auto              => TypeKind::Auto,
+            FuncType::Void              => TypeKind::Void,
+            FuncType::Int               => TypeKind::Int,
+            FuncType::Float             => TypeKind::Float,
+            FuncType::Double            => TypeKind::Double,
+            FuncType::LongDouble        => TypeKind::LongDouble,
+            FuncType::ISize             => TypeKind::ISize,
+            FuncType::USize             => TypeKind::USize,
+            FuncType::S8                => TypeKind::S8,
+            FuncType::S16               => TypeKind::S16,
+            FuncType::S32               => TypeKind::S32,
+            FuncType::S64               => TypeKind::S64,
+            FuncType::S128              => TypeKind::S128,
+            FuncType::U8                => TypeKind::U8,
+            FuncType::U16               => TypeKind::U16,
+            FuncType::U32               => TypeKind::U32,
+            FuncType::U64               => TypeKind::U64,
+            FuncType::U128              => TypeKind::U128,
+            FuncType::F32               => TypeKind::F32,
+            FuncType::F64               => TypeKind::F64,
+            FuncType::F128              => TypeKind::F128,
+            FuncType::ComplexF32        => TypeKind::ComplexF32,
+            FuncType::ComplexF64        => TypeKind::ComplexF64,
+            FuncType::ComplexLongDouble => TypeKind::ComplexLongDouble,
+            FuncType::Pointer           => TypeKind::Pointer,
+            FuncType::RefStringPtr      => TypeKind::RefStringPtr,
+            FuncType::BorrowStringPtr   => TypeKind::BorrowStringPtr,
+            FuncType::RefArrayPtr       => TypeKind::RefArrayPtr,
+            FuncType::BorrowArrayPtr    => TypeKind::BorrowArrayPtr,
+            FuncType::Struct(_)         => TypeKind::Struct,
+            FuncType::Enum(_)           => TypeKind::Enum,
+            FuncType::Flags(_)          => TypeKind::Flags
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<&StructType> {
+        match self {
+            FuncType::Struct(structure) => Some(structure),
+            _ => None
+        }
+    }
+
+    pub fn descriptor(&self) -> String {
+        let mut str = String::new();
+        self.write_descriptor(&mut str);
+        str
+    }
+
+    fn write_descriptor(&self, str: &mut String) {
+        match self {
+            FuncType::LongDouble => str.push_str("longdouble"),
+            FuncType::BorrowStringPtr => str.push_str("*str"),
+            FuncType::BorrowArrayPtr => str.push_str("*[]"),
+            FuncType::Struct(structure) => {
+                str.push('[');
+                for (i, (field, _)) in structure.fields().iter().enumerate() {
+                    if i > 0 { str.push(','); }
+                    field.write_descriptor(str);
+                }
+                str.push(']');
+            }
+            FuncType::Enum(enumeration) => str.push_str(&enumeration.to_string()),
+            FuncType::Flags(flags) => str.push_str(&flags.to_string()),
+            _ => str.push_str(&self.to_string()),
+        }
+    }
+
     pub fn is_auto(&self) -> bool {
         match self {
             FuncType::Auto => true,
@@ -242,6 +324,31 @@ impl FuncType {
         }
     }
 
+    pub fn is_void(&self) -> bool {
+        matches!(self, FuncType::Void)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            FuncType::Int | FuncType::ISize | FuncType::USize |
+            FuncType::S8 | FuncType::S16 | FuncType::S32 | FuncType::S64 |
+            FuncType::U8 | FuncType::U16 | FuncType::U32 | FuncType::U64
+        )
+    }
+
+    pub fn is_int128(&self) -> bool {
+        matches!(self, FuncType::S128 | FuncType::U128)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, FuncType::Float | FuncType::Double | FuncType::F32 | FuncType::F64)
+    }
+
+    pub fn is_complex(&self) -> bool {
+        matches!(self, FuncType::ComplexF32 | FuncType::ComplexF64 | FuncType::ComplexLongDouble)
+    }
+
     pub fn is_ptr(&self) -> bool {
         match self {
             FuncType::ISize |
@@ -283,19 +390,63 @@ impl FuncType {
             FuncType::S16               => size_of::<i16>()     as c_uint,
             FuncType::S32               => size_of::<i32>()     as c_uint,
             FuncType::S64               => size_of::<i64>()     as c_uint,
+            FuncType::S128              => size_of::<i128>()    as c_uint,
             FuncType::U8                => size_of::<u8>()      as c_uint,
             FuncType::U16               => size_of::<u16>()     as c_uint,
             FuncType::U32               => size_of::<u32>()     as c_uint,
             FuncType::U64               => size_of::<u64>()     as c_uint,
+            FuncType::U128              => size_of::<u128>()    as c_uint,
             FuncType::F32               => size_of::<f32>()     as c_uint,
             FuncType::F64               => size_of::<f64>()     as c_uint,
             FuncType::F128              => size_of::<i128>()    as c_uint,
-            FuncType::Pointer           => size_of::<isize>     as c_uint,
-            FuncType::RefStringPtr      => size_of::<isize>     as c_uint,
-            FuncType::BorrowStringPtr   => size_of::<isize>     as c_uint,
-            FuncType::RefArrayPtr       => size_of::<isize>     as c_uint,
-            FuncType::BorrowArrayPtr    => size_of::<isize>     as c_uint,
-            FuncType::Struct(structure) => structure.size() as c_uint
+            FuncType::ComplexF32        => size_of::<[f32; 2]>()    as c_uint,
+            FuncType::ComplexF64        => size_of::<[f64; 2]>()    as c_uint,
+            FuncType::ComplexLongDouble => size_of::<[i128; 2]>()   as c_uint,
+            FuncType::Pointer           => size_of::<isize>()   as c_uint,
+            FuncType::RefStringPtr      => size_of::<isize>()   as c_uint,
+            FuncType::BorrowStringPtr   => size_of::<isize>()   as c_uint,
+            FuncType::RefArrayPtr       => size_of::<isize>()   as c_uint,
+            FuncType::BorrowArrayPtr    => size_of::<isize>()   as c_uint,
+            FuncType::Struct(structure) => structure.size() as c_uint,
+            FuncType::Enum(enumeration) => enumeration.size(),
+            FuncType::Flags(flags)      => flags.size()
+        }
+    }
+
+    pub fn align(&self) -> c_uint {
+        match self {
+            FuncType::Auto              => 1                        as c_uint,
+            FuncType::Void              => 1                        as c_uint,
+            FuncType::Int               => align_of::<c_int>()      as c_uint,
+            FuncType::Float             => align_of::<c_float>()    as c_uint,
+            FuncType::Double            => align_of::<c_double>()   as c_uint,
+            FuncType::LongDouble        => align_of::<i128>()       as c_uint,
+            FuncType::ISize             => align_of::<isize>()      as c_uint,
+            FuncType::USize             => align_of::<usize>()      as c_uint,
+            FuncType::S8                => align_of::<i8>()         as c_uint,
+            FuncType::S16               => align_of::<i16>()        as c_uint,
+            FuncType::S32               => align_of::<i32>()        as c_uint,
+            FuncType::S64               => align_of::<i64>()        as c_uint,
+            FuncType::S128              => align_of::<i128>()       as c_uint,
+            FuncType::U8                => align_of::<u8>()         as c_uint,
+            FuncType::U16               => align_of::<u16>()        as c_uint,
+            FuncType::U32               => align_of::<u32>()        as c_uint,
+            FuncType::U64               => align_of::<u64>()        as c_uint,
+            FuncType::U128              => align_of::<u128>()       as c_uint,
+            FuncType::F32               => align_of::<f32>()        as c_uint,
+            FuncType::F64               => align_of::<f64>()        as c_uint,
+            FuncType::F128              => align_of::<i128>()       as c_uint,
+            FuncType::ComplexF32        => align_of::<f32>()        as c_uint,
+            FuncType::ComplexF64        => align_of::<f64>()        as c_uint,
+            FuncType::ComplexLongDouble => align_of::<i128>()       as c_uint,
+            FuncType::Pointer           => align_of::<*const c_void>() as c_uint,
+            FuncType::RefStringPtr      => align_of::<*const c_void>() as c_uint,
+            FuncType::BorrowStringPtr   => align_of::<*const c_void>() as c_uint,
+            FuncType::RefArrayPtr       => align_of::<*const c_void>() as c_uint,
+            FuncType::BorrowArrayPtr    => align_of::<*const c_void>() as c_uint,
+            FuncType::Struct(structure) => structure.align(),
+            FuncType::Enum(enumeration) => enumeration.align(),
+            FuncType::Flags(flags)      => flags.align()
         }
     }
 }
@@ -317,16 +468,22 @@ impl Display for FuncType {
             FuncType::S16 => write!(f, "i16"),
             FuncType::S32 => write!(f, "i32"),
             FuncType::S64 => write!(f, "i64"),
+            FuncType::S128 => write!(f, "i128"),
 
             FuncType::U8 => write!(f, "u8"),
             FuncType::U16 => write!(f, "u16"),
             FuncType::U32 => write!(f, "u32"),
             FuncType::U64 => write!(f, "u64"),
+            FuncType::U128 => write!(f, "u128"),
 
             FuncType::F32 => write!(f, "f32"),
             FuncType::F64 => write!(f, "f64"),
             FuncType::F128 => write!(f, "f128"),
 
+            FuncType::ComplexF32 => write!(f, "cf32"),
+            FuncType::ComplexF64 => write!(f, "cf64"),
+            FuncType::ComplexLongDouble => write!(f, "clongdouble"),
+
             FuncType::Pointer => write!(f, "*"),
             FuncType::RefStringPtr => write!(f, "&str"),
             FuncType::BorrowStringPtr => write!(f, "&mut str"),
@@ -334,11 +491,41 @@ impl Display for FuncType {
             FuncType::BorrowArrayPtr => write!(f, "&mut []"),
 
             FuncType::Struct(struct_type) => { write!(f, "{}", struct_type) }
+            FuncType::Enum(enum_type) => { write!(f, "enum {}", enum_type.name()) }
+            FuncType::Flags(flags_type) => { write!(f, "flags {}", flags_type.name()) }
         }
     }
 }
 
 
+impl FuncCif {
+    pub(crate) fn new(desc: &FuncDesc) -> Result<Self, Error> {
+        let mut helper = FuncDescHelper::new(&desc.return_type, &desc.argument_types)?;
+        let mut cif = ffi_cif::default();
+        #[allow(nonstandard_style)]
+        match unsafe {
+            ffi_prep_cif(
+                &mut cif,
+                ffi_abi_FFI_DEFAULT_ABI,
+                helper.arguments_types.len() as c_uint,
+                helper.return_type,
+                helper.arguments_types.as_mut_ptr(),
+            )
+        } {
+            ffi_status_FFI_OK => {},
+            ffi_status_FFI_BAD_TYPEDEF => return Err(Error::FFIBadTypeDef),
+            ffi_status_FFI_BAD_ABI => return Err(Error::FFIBadABI),
+            ffi_status_FFI_BAD_ARGTYPE => return Err(Error::FFIBadArgType),
+            _ => unreachable!()
+        };
+        Ok(Self { cif: UnsafeCell::new(cif), _helper: helper })
+    }
+
+    pub(crate) unsafe fn _call(&self, symbol: *const c_void, rvalue: *mut c_void, avalue: *mut *mut c_void) {
+        unsafe { ffi_call(self.cif.get(), transmute(symbol), rvalue, avalue) }
+    }
+}
+
 impl FuncDescHelper {
     fn new(return_type: &FuncType, argument_types: &[FuncType]) -> Result<Self, Error> {
         unsafe {
@@ -352,6 +539,24 @@ impl FuncDescHelper {
         }
     }
 
+    // libffi has no 128-bit integers, so `__int128` is described as a pair of 64-bit halves
+    // with the alignment preset, which matches how the x86_64 SysV ABI classifies it
+    #[cfg(all(target_arch = "x86_64", target_endian = "little", not(windows)))]
+    fn int128_into_ffi_type(&mut self, half: *mut ffi_type) -> *mut ffi_type {
+        let mut fields_array = Box::new(vec![half, half, null_mut()]);
+        let mut boxed = Box::new(
+            ffi_type {
+                size: size_of::<i128>(),
+                alignment: align_of::<i128>() as _,
+                type_: FFI_TYPE_STRUCT,
+                elements: fields_array.as_mut_ptr(),
+            }
+        );
+        let ptr = boxed.as_mut() as *mut ffi_type;
+        self._boxed.push((boxed, fields_array));
+        ptr
+    }
+
     unsafe fn type_into_ffi_type(&mut self, value: &FuncType) -> Result<*mut ffi_type, Error> {
         unsafe {
             Ok(
@@ -359,27 +564,42 @@ impl FuncDescHelper {
                     FuncType::Auto => return Error::invalid_desc_from_str("Type 'auto' not supported for call"),
                     FuncType::Void => &raw mut ffi_type_void,
 
-                    FuncType::Int => if const { size_of::<c_int>() == 32 } { &raw mut ffi_type_sint32 } else { &raw mut ffi_type_sint64 },
+                    FuncType::Int => if const { size_of::<c_int>() == 4 } { &raw mut ffi_type_sint32 } else { &raw mut ffi_type_sint64 },
                     FuncType::Float => &raw mut ffi_type_float,
                     FuncType::Double => &raw mut ffi_type_double,
                     FuncType::LongDouble => &raw mut ffi_type_longdouble,
-                    FuncType::ISize => if const { size_of::<isize>() == 32 } { &raw mut ffi_type_sint32 } else { &raw mut ffi_type_sint64 },
-                    FuncType::USize => if const { size_of::<usize>() == 32 } { &raw mut ffi_type_uint32 } else { &raw mut ffi_type_uint64 },
+                    FuncType::ISize => if const { size_of::<isize>() == 4 } { &raw mut ffi_type_sint32 } else { &raw mut ffi_type_sint64 },
+                    FuncType::USize => if const { size_of::<usize>() == 4 } { &raw mut ffi_type_uint32 } else { &raw mut ffi_type_uint64 },
 
                     FuncType::S8 => &raw mut ffi_type_sint8,
                     FuncType::S16 => &raw mut ffi_type_sint16,
                     FuncType::S32 => &raw mut ffi_type_sint32,
                     FuncType::S64 => &raw mut ffi_type_sint64,
+                    #[cfg(all(target_arch = "x86_64", target_endian = "little", not(windows)))]
+                    FuncType::S128 => self.int128_into_ffi_type(&raw mut ffi_type_sint64),
 
                     FuncType::U8 => &raw mut ffi_type_uint8,
                     FuncType::U16 => &raw mut ffi_type_uint16,
                     FuncType::U32 => &raw mut ffi_type_uint32,
                     FuncType::U64 => &raw mut ffi_type_uint64,
+                    #[cfg(all(target_arch = "x86_64", target_endian = "little", not(windows)))]
+                    FuncType::U128 => self.int128_into_ffi_type(&raw mut ffi_type_uint64),
+                    #[cfg(not(all(target_arch = "x86_64", target_endian = "little", not(windows))))]
+                    FuncType::S128 | FuncType::U128 => return Error::invalid_desc_from_string(format!("Type '{}' not supported for call on this platform", value)),
 
                     FuncType::F32 => &raw mut ffi_type_float,
                     FuncType::F64 => &raw mut ffi_type_double,
                     FuncType::F128 => &raw mut ffi_type_longdouble,
 
+                    #[cfg(not(windows))]
+                    FuncType::ComplexF32 => &raw mut ffi_type_complex_float,
+                    #[cfg(not(windows))]
+                    FuncType::ComplexF64 => &raw mut ffi_type_complex_double,
+                    #[cfg(not(windows))]
+                    FuncType::ComplexLongDouble => &raw mut ffi_type_complex_longdouble,
+                    #[cfg(windows)]
+                    FuncType::ComplexF32 | FuncType::ComplexF64 | FuncType::ComplexLongDouble => return Error::invalid_desc_from_string(format!("Type '{}' not supported for call on this platform", value)),
+
                     FuncType::Pointer => &raw mut ffi_type_pointer,
                     FuncType::RefStringPtr => &raw mut ffi_type_pointer,
                     FuncType::BorrowStringPtr => &raw mut ffi_type_pointer,
@@ -387,6 +607,7 @@ impl FuncDescHelper {
                     FuncType::BorrowArrayPtr => &raw mut ffi_type_pointer,
 
                     FuncType::Struct(structure) => {
+                        if !structure.is_natural() { return Error::invalid_desc_from_string(format!("Structure with non-natural layout can't be passed by value: {}", structure)) }
                         let fields = structure.fields();
                         let mut fields_array = Box::new(Vec::with_capacity(fields.len()));
                         for field in fields
@@ -404,6 +625,9 @@ impl FuncDescHelper {
                         self._boxed.push((boxed, fields_array));
                         ptr
                     }
+
+                    FuncType::Enum(enumeration) => self.type_into_ffi_type(enumeration.repr())?,
+                    FuncType::Flags(flags) => self.type_into_ffi_type(flags.repr())?
                 }
             )
         }
@@ -439,8 +663,66 @@ mod tests {
         assert_eq!(FuncType::from_str("[f32,[i32,[i64]]]").unwrap(), FuncType::structure(&[FuncType::F32, FuncType::structure(&[FuncType::S32, FuncType::structure(&[FuncType::S64])])]));
     }
 
+    #[test]
+    fn test_complex() {
+        assert_eq!(FuncDesc::from_str("(cf32,cf64)cf64").unwrap(), FuncDesc::new(Box::new([FuncType::ComplexF32, FuncType::ComplexF64]), FuncType::ComplexF64));
+        assert!(FuncDesc::from_str("(cf32)clongdouble").unwrap_err().to_string().contains("not supported"));
+        assert!(FuncType::from_str("[i8,clongdouble]").is_err());
+        assert_eq!(FuncType::ComplexF64.size(), 16);
+        assert_eq!(FuncType::ComplexF64.align(), 8);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_complex_call() {
+        let mut lib = crate::library::LibHandle::open("libm.so.6").unwrap();
+        let mut result = 0f64;
+        let mut value = [3f64, 4f64];
+        unsafe { lib.func("cabs", "(cf64)f64").unwrap().call(&mut result as *mut f64 as *mut c_void, &mut [&mut value as *mut [f64; 2] as *mut c_void]); }
+        assert_eq!(result, 5.0);
+        let mut result = [0f32; 2];
+        let mut value = [1f32, 2f32];
+        unsafe { lib.func("conjf", "(cf32)cf32").unwrap().call(&mut result as *mut [f32; 2] as *mut c_void, &mut [&mut value as *mut [f32; 2] as *mut c_void]); }
+        assert_eq!(result, [1.0, -2.0]);
+    }
+
+    #[test]
+    fn test_int128() {
+        assert_eq!(FuncDesc::from_str("(i128)u128").unwrap(), FuncDesc::new(Box::new([FuncType::S128]), FuncType::U128));
+        assert_eq!(FuncType::S128.size(), 16);
+        assert_eq!(FuncType::U128.align() as usize, align_of::<u128>());
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_endian = "little", not(windows)))]
+    fn test_int128_call() {
+        unsafe extern "C" fn mul(a: i128, b: u64) -> i128 { a * b as i128 }
+        let func = FuncHandle::new(mul as *const c_void, FuncDesc::from_str("(i128,u64)i128").unwrap()).unwrap();
+        let mut result = 0i128;
+        let mut a = -(1i128 << 80);
+        let mut b = 3u64;
+        unsafe { func.call(&mut result as *mut i128 as *mut c_void, &mut [&mut a as *mut i128 as *mut c_void, &mut b as *mut u64 as *mut c_void]); }
+        assert_eq!(result, -3 * (1i128 << 80));
+    }
+
+    #[test]
+    fn test_descriptor() {
+        for str in ["(i8,&str,*str,*[],longdouble)void", "([i32,[u8,cf32]],*)[f64]", "(enum Color:u8{Red=0,Green=1},flags Mode:u16{Read=0x1,Write=0x2})usize"] {
+            let desc = FuncDesc::from_str(str).unwrap();
+            assert_eq!(desc.descriptor(), str);
+            assert_eq!(FuncDesc::from_str(&desc.descriptor()).unwrap(), desc);
+        }
+        let desc = FuncDesc::from_str("([i32,f64],enum Bool{False,True})void").unwrap();
+        assert_eq!(desc.argument_types()[0].kind(), TypeKind::Struct);
+        assert_eq!(desc.argument_types()[0].as_struct().unwrap().fields().len(), 2);
+        assert_eq!(desc.argument_types()[1].kind(), TypeKind::Enum);
+        assert_eq!(desc.return_type().kind(), TypeKind::Void);
+    }
+
     #[test]
     fn test_simple_desc() {
         assert_eq!(FuncDesc::from_str("(i8,i8)i16").unwrap(), FuncDesc::new(Box::new([FuncType::S8, FuncType::S8]), FuncType::S16));
+        assert_eq!(FuncDesc::from_str("(enum Color:u8{Red,Green},i8)void").unwrap(), FuncDesc::new(Box::new([FuncType::Enum(EnumType::from_str("enum Color:u8{Red,Green}").unwrap()), FuncType::S8]), FuncType::Void));
+        assert_eq!(FuncDesc::from_str("([i8],i8)void").unwrap(), FuncDesc::new(Box::new([FuncType::structure(&[FuncType::S8]), FuncType::S8]), FuncType::Void));
     }
 }
\ No newline at end of file
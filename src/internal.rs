@@ -1,8 +1,11 @@
+use crate::allocator;
+use crate::tracking::{track, Resource};
 use std::ffi::{c_char, CStr, CString};
+use std::ptr::{copy_nonoverlapping, null_mut};
 use crate::error::Error;
 
 pub fn try_str_to_c_string(input: &str) -> Result<CString, Error> {
-    CString::new(input).map_err(|_| Error::RustStringToCString)
+    CString::new(input).map_err(Error::RustStringToCString)
 }
 
 pub unsafe fn str_to_c_mut_char_unchecked(input: &str) -> *mut c_char {
@@ -29,6 +32,14 @@ pub fn dup_c_const_char(text: *const c_char) -> *mut c_char {
     unsafe { CStr::from_ptr(text).to_owned().into_raw() }
 }
 
+pub fn strdup_c_const_char(text: *const c_char) -> *mut c_char {
+    let bytes = unsafe { CStr::from_ptr(text) }.to_bytes_with_nul();
+    let Ok(copy) = allocator::alloc(bytes.len(), 1) else { return null_mut() };
+    unsafe { copy_nonoverlapping(bytes.as_ptr(), copy as *mut u8, bytes.len()) }
+    track(Resource::String, copy);
+    copy as *mut c_char
+}
+
 pub fn free_c_mut_char(text: *mut c_char) {
     let _ = unsafe { CString::from_raw(text) };
 }
@@ -1,8 +1,20 @@
 pub mod api;
 pub mod adapter;
+pub mod allocator;
+pub mod arena;
+pub mod bound;
+pub mod codegen;
+pub mod enumeration;
 pub mod error;
+pub mod flags;
 pub mod interface;
 pub mod platform;
 pub mod internal;
 pub mod library;
+pub mod pystruct;
 pub mod structure;
+pub mod syntax;
+pub mod tracking;
+pub mod value;
+pub mod version;
+pub mod view;
@@ -1,4 +1,4 @@
-use crate::interface::FuncHandle;
+use crate::interface::{FuncDesc, FuncHandle, Syntax};
 use crate::internal::{cmp_c_const_char, dup_c_const_char, free_c_mut_char, try_str_to_c_string};
 use crate::platform::platform;
 use std::alloc::{alloc, dealloc, Layout};
@@ -32,13 +32,20 @@ impl LibHandle {
     pub fn open(name: &str) -> Result<LibHandle, Error> {
         unsafe {
             let name = try_str_to_c_string(name)?;
-            Self::_open(name.as_ptr())
+            Self::_open(name.as_ptr(), false)
         }
     }
 
-    pub(crate) unsafe fn _open(name: *const c_char) -> Result<LibHandle, Error> {
+    pub fn open_now(name: &str) -> Result<LibHandle, Error> {
         unsafe {
-            let handle = platform::open_library(name)?;
+            let name = try_str_to_c_string(name)?;
+            Self::_open(name.as_ptr(), true)
+        }
+    }
+
+    pub(crate) unsafe fn _open(name: *const c_char, now: bool) -> Result<LibHandle, Error> {
+        unsafe {
+            let handle = platform::open_library(name, now)?;
             Ok(LibHandle::new(handle))
         }
     }
@@ -55,19 +62,27 @@ impl LibHandle {
     }
 
     pub fn func(&mut self, name: &str, desc: &str) -> Result<&FuncHandle, Error> {
+        self.func_with(name, desc, Syntax::Sffi)
+    }
+
+    pub fn func_with(&mut self, name: &str, desc: &str, syntax: Syntax) -> Result<&FuncHandle, Error> {
         unsafe {
             let name = try_str_to_c_string(name)?;
-            let func = self._func(name.as_ptr(), desc)?;
+            let func = self._func_with(name.as_ptr(), desc, syntax)?;
             Ok(&*func)
         }
     }
 
     pub(crate) unsafe fn _func(&mut self, name: *const c_char, desc: &str) -> Result<*const FuncHandle, Error> {
+        unsafe { self._func_with(name, desc, Syntax::Sffi) }
+    }
+
+    pub(crate) unsafe fn _func_with(&mut self, name: *const c_char, desc: &str, syntax: Syntax) -> Result<*const FuncHandle, Error> {
         unsafe {
             let find = self.find_function(name);
             if !find.is_null() { return Ok(find) }
             let symbol = self._symbol(name)?;
-            let func = FuncHandle::new(symbol, desc)?;
+            let func = FuncDesc::parse_with(syntax, desc).and_then(|desc| FuncHandle::new(symbol, desc)).map_err(|it| it.with_function(name))?;
             let node = alloc(Layout::new::<LibHandleFuncList>()) as *mut LibHandleFuncList;
             (*node).name = dup_c_const_char(name);
             (*node).func = ManuallyDrop::new(func);
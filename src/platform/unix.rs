@@ -1,4 +1,4 @@
-use crate::error::Error;
+use crate::error::{optional_c_const_char, Error};
 use std::ffi::{c_char, c_int, c_void};
 
 unsafe extern "C" {
@@ -9,21 +9,21 @@ unsafe extern "C" {
 }
 
 const RTLD_LAZY: c_int = 0x0001;
-// const RTLD_NOW: c_int = 0x0002;
+const RTLD_NOW: c_int = 0x0002;
 const RTDL_LOCAL: c_int = 0x0000;
-// const RTLD_GLOBAL: c_int = 0x0100;
 
-pub unsafe fn open_library(path: *const c_char) -> Result<*mut c_void, Error> {
+pub unsafe fn open_library(path: *const c_char, now: bool) -> Result<*mut c_void, Error> {
     unsafe {
-        let result = dlopen(path, RTLD_LAZY | RTDL_LOCAL);
-        if result.is_null() { return Error::lib_open_from_cstr(dlerror()) }
+        // Binding every symbol up front reports undefined ones here instead of crashing on their first call
+        let result = dlopen(path, if now { RTLD_NOW } else { RTLD_LAZY } | RTDL_LOCAL);
+        if result.is_null() { return Err(classify_open_error(optional_c_const_char(path), optional_c_const_char(dlerror()))) }
         Ok(result)
     }
 }
 pub unsafe fn get_symbol(handle: *mut c_void, name: *const c_char) -> Result<*const c_void, Error> {
     unsafe {
         let result = dlsym(handle, name);
-        if result.is_null() { return Error::lib_symbol_from_cstr(dlerror()) }
+        if result.is_null() { return Error::lib_symbol_from_cstr(name, dlerror()) }
         Ok(result)
     }
 }
@@ -36,4 +36,106 @@ pub unsafe fn close_library(handle: *mut c_void) -> Result<(), Error> {
             Error::lib_close_from_cstr(dlerror())
         }
     }
-}
\ No newline at end of file
+}
+
+fn classify_open_error(path: Option<String>, message: Option<String>) -> Error {
+    let text = if let Some(text) = message.as_deref() { text } else { return Error::LibraryOpen { path, message } };
+
+    if let Some(symbol) = undefined_symbol(text) {
+        return Error::LibraryUndefinedSymbol { path, symbol: Some(symbol.into()), message }
+    }
+    if ["wrong ELF class", "invalid ELF header", "ELF file", "Exec format error", "incompatible architecture"].iter().any(|it| text.contains(it)) {
+        return Error::LibraryWrongArch { path, message }
+    }
+    if ["Permission denied", "Operation not permitted"].iter().any(|it| text.contains(it)) {
+        return Error::LibraryPermissionDenied { path, message }
+    }
+    if let Some(dependency) = missing_dependency(text, path.as_deref()) {
+        return Error::LibraryMissingDependency { path, dependency: Some(dependency.into()), message }
+    }
+    if ["No such file or directory", "image not found", "no such file"].iter().any(|it| text.contains(it)) {
+        return Error::LibraryNotFound { path, message }
+    }
+    Error::LibraryOpen { path, message }
+}
+
+fn undefined_symbol(text: &str) -> Option<&str> {
+    // glibc: "<file>: undefined symbol: <symbol>[, version <version>]"
+    if let Some((_, symbol)) = text.split_once("undefined symbol: ") {
+        return symbol.split(',').next().map(str::trim)
+    }
+    // musl: "Error relocating <file>: <symbol>: symbol not found"
+    if let Some(text) = text.strip_suffix(": symbol not found") {
+        return text.rsplit_once(": ").map(|(_, symbol)| symbol.trim())
+    }
+    // dyld: "Symbol not found: <symbol>\n  Referenced from: ..."
+    if let Some((_, symbol)) = text.split_once("Symbol not found: ") {
+        return symbol.split_whitespace().next()
+    }
+    None
+}
+
+fn missing_dependency<'a>(text: &'a str, path: Option<&str>) -> Option<&'a str> {
+    // glibc: "<file>: cannot open shared object file: No such file or directory"
+    if let Some((file, _)) = text.split_once(": cannot open shared object file") {
+        return if Some(file) != path { Some(file) } else { None }
+    }
+    // musl: "Error loading shared library <file>: No such file or directory (needed by <library>)"
+    if let Some(text) = text.strip_prefix("Error loading shared library ") && text.contains("(needed by ") {
+        return text.split_once(": ").map(|(file, _)| file)
+    }
+    // dyld: "dlopen(<library>, 1): Library not loaded: <file>\n  Referenced from: ..."
+    if let Some((_, file)) = text.split_once("Library not loaded: ") {
+        return file.lines().next().map(str::trim)
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::LibHandle;
+
+    fn classify(path: &str, message: &str) -> Error {
+        classify_open_error(Some(path.into()), Some(message.into()))
+    }
+
+    #[test]
+    fn test_classify() {
+        assert!(matches!(classify("libfoo.so", "libfoo.so: cannot open shared object file: No such file or directory"), Error::LibraryNotFound { .. }));
+        assert!(matches!(classify("./libfoo.so", "./libfoo.so: wrong ELF class: ELFCLASS32"), Error::LibraryWrongArch { .. }));
+        assert!(matches!(classify("./libfoo.so", "./libfoo.so: cannot open shared object file: Permission denied"), Error::LibraryPermissionDenied { .. }));
+        assert!(matches!(classify("./libfoo.so", "libbar.so: cannot open shared object file: No such file or directory"),
+            Error::LibraryMissingDependency { dependency: Some(it), .. } if it == "libbar.so"));
+        assert!(matches!(classify("./libfoo.so", "Error loading shared library libbar.so: No such file or directory (needed by ./libfoo.so)"),
+            Error::LibraryMissingDependency { dependency: Some(it), .. } if it == "libbar.so"));
+        assert!(matches!(classify("./libfoo.so", "./libfoo.so: undefined symbol: bar_init, version BAR_1.0"),
+            Error::LibraryUndefinedSymbol { symbol: Some(it), .. } if it == "bar_init"));
+        assert!(matches!(classify("./libfoo.so", "Error relocating ./libfoo.so: bar_init: symbol not found"),
+            Error::LibraryUndefinedSymbol { symbol: Some(it), .. } if it == "bar_init"));
+        assert!(matches!(classify("./libfoo.so", "./libfoo.so: file too short"), Error::LibraryOpen { .. }));
+    }
+
+    #[test]
+    fn test_not_found() {
+        let error = LibHandle::open("libsffi_missing_library.so").unwrap_err();
+        assert!(matches!(error, Error::LibraryNotFound { path: Some(it), .. } if it == "libsffi_missing_library.so"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_undefined_symbol() {
+        let directory = std::env::temp_dir().join(format!("sffi-undefined-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let (source, library) = (directory.join("undefined.c"), directory.join("libundefined.so"));
+        std::fs::write(&source, "void sffi_missing_function(void);\nvoid call(void) { sffi_missing_function(); }\n").unwrap();
+        let status = std::process::Command::new("cc").args(["-shared", "-fPIC"]).arg(&source).arg("-o").arg(&library).status().unwrap();
+        assert!(status.success());
+
+        let error = LibHandle::open_now(library.to_str().unwrap()).unwrap_err();
+        let lazy = LibHandle::open(library.to_str().unwrap());
+        drop(lazy.unwrap());
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(error, Error::LibraryUndefinedSymbol { symbol: Some(it), .. } if it == "sffi_missing_function"));
+    }
+}
@@ -1,6 +1,7 @@
 use std::ffi::{c_char, c_void, CStr};
+use std::path::Path;
 use std::ptr::{null, null_mut};
-use crate::error::Error;
+use crate::error::{optional_c_const_char, Error};
 
 unsafe extern "system" {
     fn LoadLibraryA(name: *const c_char) -> *mut c_void;
@@ -15,17 +16,39 @@ const FORMAT_MESSAGE_ALLOCATE_BUFFER: u32 = 0x00000100;
 const FORMAT_MESSAGE_FROM_SYSTEM: u32 = 0x00001000;
 const FORMAT_MESSAGE_IGNORE_INSERTS: u32 = 0x00000200;
 
-pub unsafe fn open_library(path: *const c_char) -> Result<*mut c_void, Error> {
+const ERROR_FILE_NOT_FOUND: u32 = 2;
+const ERROR_PATH_NOT_FOUND: u32 = 3;
+const ERROR_ACCESS_DENIED: u32 = 5;
+const ERROR_MOD_NOT_FOUND: u32 = 126;
+const ERROR_PROC_NOT_FOUND: u32 = 127;
+const ERROR_BAD_EXE_FORMAT: u32 = 193;
+
+pub unsafe fn open_library(path: *const c_char, _now: bool) -> Result<*mut c_void, Error> {
     unsafe {
         let result = LoadLibraryA(path);
-        if result.is_null() { return Error::lib_open_from_string(get_error_message(GetLastError())) }
+        if result.is_null() {
+            let error_code = GetLastError();
+            let (path, message) = (optional_c_const_char(path), Some(get_error_message(error_code)));
+            return Err(
+                match error_code {
+                    ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND => Error::LibraryNotFound { path, message },
+                    // The loader reports a missing dependency with the same code as a missing library.
+                    ERROR_MOD_NOT_FOUND if path.as_deref().is_some_and(|it| Path::new(it).exists()) => Error::LibraryMissingDependency { path, dependency: None, message },
+                    ERROR_MOD_NOT_FOUND => Error::LibraryNotFound { path, message },
+                    ERROR_BAD_EXE_FORMAT => Error::LibraryWrongArch { path, message },
+                    ERROR_PROC_NOT_FOUND => Error::LibraryUndefinedSymbol { path, symbol: None, message },
+                    ERROR_ACCESS_DENIED => Error::LibraryPermissionDenied { path, message },
+                    _ => Error::LibraryOpen { path, message },
+                }
+            )
+        }
         Ok(result)
     }
 }
 pub unsafe fn get_symbol(handle: *mut c_void, name: *const c_char) -> Result<*mut c_void, Error> {
     unsafe {
         let result = GetProcAddress(handle, name);
-        if result.is_null() { return Error::lib_symbol_from_string(get_error_message(GetLastError())) }
+        if result.is_null() { return Err(Error::LibrarySymbol { symbol: optional_c_const_char(name), message: Some(get_error_message(GetLastError())) }) }
         Ok(result)
     }
 }
@@ -33,7 +56,7 @@ pub unsafe fn get_symbol(handle: *mut c_void, name: *const c_char) -> Result<*mu
 pub unsafe fn close_library(handle: *mut c_void) -> Result<(), Error> {
     unsafe {
         if FreeLibrary(handle) == 0 {
-            Error::lib_close_from_string(get_error_message(GetLastError()))
+            Err(Error::LibraryClose { message: Some(get_error_message(GetLastError())) })
         } else {
             Ok(())
         }
@@ -0,0 +1,157 @@
+use crate::error::Error;
+use crate::interface::FuncType;
+use crate::structure::StructType;
+use std::cmp::max;
+use std::ffi::{c_long, c_uint};
+
+const MAX_REPEAT: c_uint = 1 << 16;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Native, Standard
+}
+
+impl StructType {
+    pub fn from_pystruct(fmt: &str) -> Result<Self, Error> {
+        Self::_from_pystruct(fmt).map_err(|it| it.with_descriptor(fmt))
+    }
+
+    fn _from_pystruct(fmt: &str) -> Result<Self, Error> {
+        let (mode, fmt) = match fmt.chars().next() {
+            Some('@') => (Mode::Native, &fmt[1..]),
+            Some('=') => (Mode::Standard, &fmt[1..]),
+            Some('<') if cfg!(target_endian = "little") => (Mode::Standard, &fmt[1..]),
+            Some('>' | '!') if cfg!(target_endian = "big") => (Mode::Standard, &fmt[1..]),
+            Some(prefix @ ('<' | '>' | '!')) => return Error::invalid_desc_from_string(format!("Byte order '{}' is not native", prefix)),
+            _ => (Mode::Native, fmt),
+        };
+
+        let mut fields = Vec::new();
+        let mut offset = 0 as c_uint;
+        let mut align = 1 as c_uint;
+        let mut chars = fmt.chars().peekable();
+        while let Some(char) = chars.next() {
+            if char.is_whitespace() { continue }
+            let (count, char) = if let Some(digit) = char.to_digit(10) {
+                let mut count = digit;
+                while let Some(digit) = chars.peek().and_then(|it| it.to_digit(10)) {
+                    let Some(next) = count.checked_mul(10).and_then(|it| it.checked_add(digit)).filter(|it| *it <= MAX_REPEAT) else {
+                        return Error::invalid_desc_from_string(format!("Repeat count exceeds {}", MAX_REPEAT))
+                    };
+                    count = next;
+                    chars.next();
+                }
+                match chars.next() {
+                    Some(char) => (count, char),
+                    None => return Error::invalid_desc_from_str("Repeat count without format character")
+                }
+            } else {
+                (1, char)
+            };
+
+            match char {
+                'x' => offset = advance(offset, 1, count)?,
+                's' | 'p' => if count > 0 {
+                    fields.push((FuncType::Struct(StructType::new(vec![FuncType::U8; count as usize].into_boxed_slice())), offset));
+                    offset = advance(offset, 1, count)?;
+                },
+                _ => {
+                    let field = Self::pystruct_type(char, mode)?;
+                    let field_align = if mode == Mode::Native { field.align() } else { 1 };
+                    align = max(align, field_align);
+                    for _ in 0..count {
+                        offset = advance(offset, field_align, 0)?;
+                        fields.push((field.clone(), offset));
+                        offset = advance(offset, 1, field.size())?;
+                    }
+                }
+            }
+        }
+
+        // Unlike `struct.calcsize`, the native mode pads the tail like a C compiler does,
+        // so the layout can be used for arrays and by-value arguments.
+        Ok(StructType::with_layout(fields.into_boxed_slice(), advance(offset, align, 0)?, align))
+    }
+
+    fn pystruct_type(char: char, mode: Mode) -> Result<FuncType, Error> {
+        Ok(
+            match char {
+                'c' | 'B' | '?' => FuncType::U8,
+                'b'             => FuncType::S8,
+                'h'             => FuncType::S16,
+                'H'             => FuncType::U16,
+                'i' if mode == Mode::Native => FuncType::Int,
+                'i'             => FuncType::S32,
+                'I'             => FuncType::U32,
+                'l' if mode == Mode::Native && size_of::<c_long>() == 8 => FuncType::S64,
+                'L' if mode == Mode::Native && size_of::<c_long>() == 8 => FuncType::U64,
+                'l'             => FuncType::S32,
+                'L'             => FuncType::U32,
+                'q'             => FuncType::S64,
+                'Q'             => FuncType::U64,
+                'f'             => FuncType::F32,
+                'd'             => FuncType::F64,
+                'n' if mode == Mode::Native => FuncType::ISize,
+                'N' if mode == Mode::Native => FuncType::USize,
+                'P' if mode == Mode::Native => FuncType::Pointer,
+                'n' | 'N' | 'P' => return Error::invalid_desc_from_string(format!("Format '{}' is only available in native mode", char)),
+                _ => return Error::invalid_desc_from_string(format!("Unsupported format character: {}", char))
+            }
+        )
+    }
+}
+
+fn advance(offset: c_uint, align: c_uint, size: c_uint) -> Result<c_uint, Error> {
+    match offset.checked_next_multiple_of(align).and_then(|it| it.checked_add(size)) {
+        Some(offset) => Ok(offset),
+        None => Error::invalid_desc_from_str("Structure is too large"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(struct_type: &StructType) -> Vec<c_uint> {
+        struct_type.fields().iter().map(|(_, offset)| *offset).collect()
+    }
+
+    #[test]
+    fn test_native() {
+        let struct_type = StructType::from_pystruct("@bih").unwrap();
+        assert_eq!(struct_type, StructType::from_str("[i8,int,i16]").unwrap());
+        assert!(struct_type.is_natural());
+
+        let struct_type = StructType::from_pystruct("b4xi").unwrap();
+        assert_eq!(offsets(&struct_type), [0, 8]);
+        assert_eq!(struct_type.size(), 12);
+        assert!(!struct_type.is_natural());
+    }
+
+    #[test]
+    fn test_standard() {
+        let struct_type = StructType::from_pystruct("=iIhq8s").unwrap();
+        assert_eq!(offsets(&struct_type), [0, 4, 8, 10, 18]);
+        assert_eq!(struct_type.size(), 26);
+        assert_eq!(struct_type.align(), 1);
+        assert_eq!(struct_type.fields()[4].0, FuncType::structure(&vec![FuncType::U8; 8]));
+    }
+
+    #[test]
+    fn test_repeat() {
+        assert_eq!(StructType::from_pystruct("3i").unwrap(), StructType::from_str("[int,int,int]").unwrap());
+        assert_eq!(StructType::from_pystruct(" 2h 2b ").unwrap(), StructType::from_str("[i16,i16,i8,i8]").unwrap());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(StructType::from_pystruct(if cfg!(target_endian = "little") { ">i" } else { "<i" }).is_err());
+        assert!(StructType::from_pystruct("!i").is_err() || cfg!(target_endian = "big"));
+        assert!(StructType::from_pystruct("=P").is_err());
+        assert!(StructType::from_pystruct("e").is_err());
+        assert!(StructType::from_pystruct("4").is_err());
+        assert!(StructType::from_pystruct("99999999999i").is_err());
+        assert!(StructType::from_pystruct("65537s").is_err());
+        assert!(StructType::from_pystruct(&"65536x".repeat(65536)).is_err());
+    }
+}
@@ -1,26 +1,95 @@
+use crate::allocator;
+use crate::enumeration::EnumType;
 use crate::interface::FuncType;
 use std::ffi::{c_uint, c_void};
+use std::cmp::max;
 use std::{fmt, ptr};
 use std::fmt::{Display, Formatter};
 use crate::error::Error;
+use crate::flags::FlagsType;
+use crate::view::StructView;
+
+macro_rules! typed_fields {
+    ($callback:ident) => {
+        $callback! {
+            FuncType::S8 => get_i8, set_i8, i8, "i8";
+            FuncType::S16 => get_i16, set_i16, i16, "i16";
+            FuncType::S32 | FuncType::Int => get_i32, set_i32, i32, "i32";
+            FuncType::S64 => get_i64, set_i64, i64, "i64";
+            FuncType::S128 => get_i128, set_i128, i128, "i128";
+            FuncType::U8 => get_u8, set_u8, u8, "u8";
+            FuncType::U16 => get_u16, set_u16, u16, "u16";
+            FuncType::U32 => get_u32, set_u32, u32, "u32";
+            FuncType::U64 => get_u64, set_u64, u64, "u64";
+            FuncType::U128 => get_u128, set_u128, u128, "u128";
+            FuncType::ISize => get_isize, set_isize, isize, "isize";
+            FuncType::USize => get_usize, set_usize, usize, "usize";
+            FuncType::F32 | FuncType::Float => get_f32, set_f32, f32, "f32";
+            FuncType::F64 | FuncType::Double => get_f64, set_f64, f64, "f64";
+            FuncType::Pointer | FuncType::RefStringPtr | FuncType::BorrowStringPtr | FuncType::RefArrayPtr | FuncType::BorrowArrayPtr => get_ptr, set_ptr, *mut c_void, "*";
+        }
+    };
+}
+
+pub(crate) use typed_fields;
+
+macro_rules! accessors {
+    ($($kind:pat => $get:ident, $set:ident, $type:ty, $name:literal;)+) => {
+        impl StructType {
+            $(
+                /// # Safety
+                ///
+                /// `structure` must point to an instance of this type that is readable for its whole size.
+                pub unsafe fn $get(&self, structure: *const c_void, index: usize) -> Result<$type, Error> {
+                    let offset = self.typed_offset(index, $name, |it| matches!(it, $kind))?;
+                    unsafe { Ok(structure.byte_add(offset).cast::<$type>().read_unaligned()) }
+                }
+
+                /// # Safety
+                ///
+                /// `structure` must point to an instance of this type that is writable for its whole size.
+                pub unsafe fn $set(&self, structure: *mut c_void, index: usize, value: $type) -> Result<(), Error> {
+                    let offset = self.typed_offset(index, $name, |it| matches!(it, $kind))?;
+                    unsafe { structure.byte_add(offset).cast::<$type>().write_unaligned(value) }
+                    Ok(())
+                }
+            )+
+        }
+    };
+}
+
+typed_fields!(accessors);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct StructType(Box<[(FuncType, c_uint)]>, c_uint);
+pub struct StructType(Box<[(FuncType, c_uint)]>, c_uint, c_uint);
 
 impl StructType {
     pub(crate) fn new(fields: Box<[FuncType]>) -> Self {
         let mut offset = 0 as c_uint;
-        let mut fields_with_offset = Box::new(Vec::with_capacity(fields.len()));
+        let mut align = 1 as c_uint;
+        let mut fields_with_offset = Vec::with_capacity(fields.len());
         for field in fields {
+            let field_align = field.align();
+            offset = align_up(offset, field_align);
+            align = max(align, field_align);
             let size = field.size();
             fields_with_offset.push((field, offset));
             offset += size;
         }
-        Self(fields_with_offset.into_boxed_slice(), offset)
+        Self(fields_with_offset.into_boxed_slice(), align_up(offset, align), align)
+    }
+
+    pub(crate) fn with_layout(fields: Box<[(FuncType, c_uint)]>, size: c_uint, align: c_uint) -> Self {
+        Self(fields, size, align)
+    }
+
+    pub(crate) fn is_natural(&self) -> bool {
+        let natural = Self::new(self.0.iter().map(|(field, _)| field.clone()).collect());
+        natural == *self
     }
 
     pub fn from_str(str: &str) -> Result<Self, Error> {
-        Ok(Self::_from_str(str)?.1)
+        Ok(Self::_from_str(str).map_err(|it| it.with_descriptor(str))?.1)
     }
 
     pub(crate) fn _from_str(str: &str) -> Result<(&str, Self), Error> {
@@ -31,9 +100,16 @@ impl StructType {
                 let (str_, value) = Self::_from_str(str)?;
                 str = str_;
                 fields.push(FuncType::Struct(value));
+            } else if str.starts_with("enum ") {
+                let (str_, value) = EnumType::_from_str(str)?;
+                str = str_;
+                fields.push(FuncType::Enum(value));
+            } else if str.starts_with("flags ") {
+                let (str_, value) = FlagsType::_from_str(str)?;
+                str = str_;
+                fields.push(FuncType::Flags(value));
             } else {
-                let next = if let Some(next) = str.find(",") { Some(next) } else { str.find("]") };
-                let next = if let Some(next) = next { next } else { return Error::invalid_desc_from_str("Struct without end") };
+                let next = if let Some(next) = str.find([',', ']']) { next } else { return Error::invalid_desc_from_str("Struct without end") };
                 let (_, value) = FuncType::__from_str(&str[..next])?;
                 fields.push(value);
                 str = &str[next..];
@@ -46,22 +122,86 @@ impl StructType {
         Ok((&str[1..], StructType::new(fields.into_boxed_slice())))
     }
 
-    pub(crate) fn fields(&self) -> &[(FuncType, c_uint)] {
+    pub fn fields(&self) -> &[(FuncType, c_uint)] {
         &self.0
     }
 
+    pub fn field_count(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn field(&self, index: usize) -> Option<&FuncType> {
+        self.0.get(index).map(|(field, _)| field)
+    }
+
+    pub fn offset(&self, index: usize) -> Option<c_uint> {
+        self.0.get(index).map(|(_, offset)| *offset)
+    }
+
     pub fn size(&self) -> c_uint {
         self.1
     }
 
+    /// # Safety
+    ///
+    /// `structure` must point to an instance of this type that outlives the returned view.
+    pub unsafe fn get_struct_view(&self, structure: *const c_void, index: usize) -> Result<StructView<'_>, Error> {
+        let (struct_type, offset) = self.struct_field(index)?;
+        unsafe { Ok(StructView::new(struct_type, structure.byte_add(offset))) }
+    }
+
+    pub(crate) fn struct_field(&self, index: usize) -> Result<(&StructType, usize), Error> {
+        let offset = self.typed_offset(index, "[]", |it| matches!(it, FuncType::Struct(_)))?;
+        match self.field(index) {
+            Some(FuncType::Struct(struct_type)) => Ok((struct_type, offset)),
+            _ => unreachable!()
+        }
+    }
+
+    fn typed_offset(&self, index: usize, name: &str, accepts: impl Fn(&FuncType) -> bool) -> Result<usize, Error> {
+        let (field, offset) = if let Some(field) = self.0.get(index) { field } else { return Error::invalid_args_from_string(format!("Invalid index ({} / {})", index, self.0.len())) };
+        if !accepts(field) { return Err(Error::InvalidCast { from: Some(field.to_string()), into: Some(name.into()), message: Some(format!("Field {} has another type", index)) }) }
+        Ok(*offset as usize)
+    }
+
+    pub fn align(&self) -> c_uint {
+        self.2
+    }
+
     pub fn malloc(&self) -> *mut c_void {
-        unsafe extern "C" { fn malloc(size: c_uint) -> *mut c_void; }
-        unsafe { malloc(self.size()) }
+        self.try_malloc().unwrap_or(ptr::null_mut())
+    }
+
+    pub fn try_malloc(&self) -> Result<*mut c_void, Error> {
+        allocator::alloc(self.size() as usize, self.align() as usize)
     }
 
     pub fn calloc(&self) -> *mut c_void {
-        unsafe extern "C" { fn calloc(size: c_uint, count: c_uint) -> *mut c_void; }
-        unsafe { calloc(self.size(), 1) }
+        self.try_calloc().unwrap_or(ptr::null_mut())
+    }
+
+    pub fn try_calloc(&self) -> Result<*mut c_void, Error> {
+        allocator::alloc_zeroed(self.size() as usize, self.align() as usize)
+    }
+
+    pub fn stride(&self) -> c_uint {
+        self.1
+    }
+
+    pub fn alloc_array(&self, count: usize) -> *mut c_void {
+        self.try_alloc_array(count).unwrap_or(ptr::null_mut())
+    }
+
+    pub fn try_alloc_array(&self, count: usize) -> Result<*mut c_void, Error> {
+        let size = if let Some(size) = count.checked_mul(self.stride() as usize) { size } else { return Error::invalid_args_from_string(format!("Array of {} elements of {} is too large", count, self)) };
+        allocator::alloc_zeroed(size, self.align() as usize)
+    }
+
+    /// # Safety
+    ///
+    /// `array` must hold more than `index` elements of this type.
+    pub unsafe fn element(&self, array: *const c_void, index: usize) -> *const c_void {
+        unsafe { array.byte_add(index * self.stride() as usize) }
     }
 
     pub unsafe fn set_raw(&self, structure: *mut c_void, index: c_uint, avalue: *const c_void) {
@@ -89,8 +229,7 @@ impl StructType {
     }
 
     pub fn free(ptr: *mut c_void) {
-        unsafe extern "C" { fn free(ptr: *mut c_void); }
-        unsafe { free(ptr); }
+        allocator::free(ptr)
     }
 }
 
@@ -105,6 +244,10 @@ impl Display for StructType {
     }
 }
 
+pub(crate) fn align_up(offset: c_uint, align: c_uint) -> c_uint {
+    offset.div_ceil(align) * align
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,5 +263,70 @@ mod tests {
         assert_eq!(FuncType::Struct(StructType::from_str("[[]]").unwrap()), FuncType::structure(&[FuncType::structure(&[])]));
         assert_eq!(FuncType::Struct(StructType::from_str("[i32,[f32]]").unwrap()), FuncType::structure(&[FuncType::S32, FuncType::structure(&[FuncType::F32])]));
         assert_eq!(FuncType::Struct(StructType::from_str("[[],[]]").unwrap()), FuncType::structure(&[FuncType::structure(&[]), FuncType::structure(&[])]));
+        assert_eq!(FuncType::Struct(StructType::from_str("[enum Bool:u8{False,True},i32]").unwrap()), FuncType::structure(&[FuncType::Enum(EnumType::from_str("enum Bool:u8{False,True}").unwrap()), FuncType::S32]));
+        assert_eq!(FuncType::Struct(StructType::from_str("[[i8],i32]").unwrap()), FuncType::structure(&[FuncType::structure(&[FuncType::S8]), FuncType::S32]));
+    }
+
+    #[test]
+    fn test_layout() {
+        #[repr(C)]
+        struct Layout(i8, i32, i16, f64, u8);
+        let struct_type = StructType::from_str("[i8,i32,i16,f64,u8]").unwrap();
+        let offsets = struct_type.fields().iter().map(|(_, offset)| *offset as usize).collect::<Vec<_>>();
+        assert_eq!(offsets, [0, 4, 8, 16, 24]);
+        assert_eq!(struct_type.size() as usize, size_of::<Layout>());
+        assert_eq!(struct_type.align() as usize, align_of::<Layout>());
+    }
+
+    #[test]
+    fn test_fields() {
+        let struct_type = StructType::from_str("[i8,[u16,f64],*]").unwrap();
+        assert_eq!(struct_type.field_count(), 3);
+        assert_eq!(struct_type.field(0), Some(&FuncType::S8));
+        assert_eq!(struct_type.offset(1), Some(8));
+        assert_eq!(struct_type.field(1).and_then(FuncType::as_struct).map(StructType::size), Some(16));
+        assert_eq!(struct_type.offset(2), Some(24));
+        assert_eq!(struct_type.field(3), None);
+        assert_eq!(struct_type.offset(3), None);
+    }
+
+    #[test]
+    fn test_typed() {
+        let struct_type = StructType::from_str("[i8,int,[u16,f64],*str]").unwrap();
+        let structure = struct_type.calloc();
+        unsafe {
+            struct_type.set_i8(structure, 0, -3).unwrap();
+            struct_type.set_i32(structure, 1, 1 << 20).unwrap();
+            struct_type.set_ptr(structure, 3, structure).unwrap();
+            assert_eq!(struct_type.get_i8(structure, 0).unwrap(), -3);
+            assert_eq!(struct_type.get_i32(structure, 1).unwrap(), 1 << 20);
+            assert_eq!(struct_type.get_ptr(structure, 3).unwrap(), structure);
+
+            let view = struct_type.get_struct_view(structure, 2).unwrap();
+            view.struct_type().set_f64(view.as_ptr() as *mut c_void, 1, 2.5).unwrap();
+            assert_eq!(view.get_f64(1).unwrap(), 2.5);
+            assert_eq!(struct_type.get_i8(structure, 0).unwrap(), -3);
+
+            let error = struct_type.get_f32(structure, 1).unwrap_err();
+            assert!(matches!(&error, Error::InvalidCast { from: Some(from), into: Some(into), .. } if from == "int" && into == "f32"));
+            assert!(matches!(struct_type.set_u8(structure, 4, 0).unwrap_err(), Error::InvalidArguments { .. }));
+            assert!(matches!(struct_type.get_struct_view(structure, 0).unwrap_err(), Error::InvalidCast { .. }));
+        }
+        StructType::free(structure);
+    }
+
+    #[test]
+    fn test_array() {
+        #[repr(C)]
+        struct Element(i64, i8);
+        let struct_type = StructType::from_str("[i64,i8]").unwrap();
+        assert_eq!(struct_type.stride() as usize, size_of::<Element>());
+        let array = struct_type.alloc_array(3);
+        unsafe {
+            struct_type.set_i8(struct_type.element(array, 2) as *mut c_void, 1, 5).unwrap();
+            assert_eq!((*(array as *const Element).add(2)).1, 5);
+        }
+        StructType::free(array);
+        assert!(struct_type.alloc_array(usize::MAX).is_null());
     }
 }
\ No newline at end of file
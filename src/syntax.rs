@@ -0,0 +1,109 @@
+use crate::error::Error;
+use crate::interface::{FuncDesc, FuncType};
+use std::ffi::c_long;
+use std::str::Chars;
+
+impl FuncDesc {
+    pub(crate) fn from_jni(str: &str) -> Result<FuncDesc, Error> {
+        let mut chars = str.trim().chars();
+        if chars.next() != Some('(') { return Error::invalid_desc_from_str("Invalid descriptor start") }
+        let mut argument_types = vec![];
+        loop {
+            match chars.clone().next() {
+                Some(')') => { chars.next(); break }
+                Some(_) => argument_types.push(Self::jni_type(&mut chars)?),
+                None => return Error::invalid_desc_from_str("Invalid descriptor end"),
+            }
+        }
+        let return_type = Self::jni_type(&mut chars)?;
+        if !chars.as_str().is_empty() { return Error::invalid_desc_from_string(format!("Unexpected descriptor tail: {}", chars.as_str())) }
+        Ok(FuncDesc::new(argument_types.into_boxed_slice(), return_type))
+    }
+
+    fn jni_type(chars: &mut Chars) -> Result<FuncType, Error> {
+        Ok(
+            match chars.next() {
+                Some('V') => FuncType::Void,
+                Some('Z') => FuncType::U8,
+                Some('B') => FuncType::S8,
+                Some('C') => FuncType::U16,
+                Some('S') => FuncType::S16,
+                Some('I') => FuncType::S32,
+                Some('J') => FuncType::S64,
+                Some('F') => FuncType::F32,
+                Some('D') => FuncType::F64,
+                Some('L') => {
+                    if !chars.any(|it| it == ';') { return Error::invalid_desc_from_str("Class name without end") }
+                    FuncType::Pointer
+                }
+                Some('[') => {
+                    if Self::jni_type(chars)? == FuncType::Void { return Error::invalid_desc_from_str("Array of void") }
+                    FuncType::Pointer
+                }
+                Some(char) => return Error::invalid_desc_from_string(format!("Unknown type: {}", char)),
+                None => return Error::invalid_desc_from_str("Missing type"),
+            }
+        )
+    }
+
+    pub(crate) fn from_dyncall(str: &str) -> Result<FuncDesc, Error> {
+        let str = str.trim();
+        let end_index = if let Some(idx) = str.find(')') { idx } else { return Error::invalid_desc_from_str("Invalid descriptor end") };
+        let argument_types = str[..end_index].chars().map(Self::dyncall_type).collect::<Result<Vec<_>, _>>()?;
+        let mut return_type = str[end_index + 1..].chars();
+        let return_type = match (return_type.next(), return_type.next()) {
+            (Some(char), None) => Self::dyncall_type(char)?,
+            _ => return Error::invalid_desc_from_string(format!("Invalid return type: {}", &str[end_index + 1..])),
+        };
+        Ok(FuncDesc::new(argument_types.into_boxed_slice(), return_type))
+    }
+
+    fn dyncall_type(char: char) -> Result<FuncType, Error> {
+        Ok(
+            match char {
+                'v' => FuncType::Void,
+                'B' => FuncType::Int,
+                'c' => FuncType::S8,
+                'C' => FuncType::U8,
+                's' => FuncType::S16,
+                'S' => FuncType::U16,
+                'i' => FuncType::Int,
+                'I' => FuncType::U32,
+                'j' => if size_of::<c_long>() == 8 { FuncType::S64 } else { FuncType::S32 },
+                'J' => if size_of::<c_long>() == 8 { FuncType::U64 } else { FuncType::U32 },
+                'l' => FuncType::S64,
+                'L' => FuncType::U64,
+                'f' => FuncType::F32,
+                'd' => FuncType::F64,
+                'p' => FuncType::Pointer,
+                'Z' => FuncType::RefStringPtr,
+                '_' => return Error::invalid_desc_from_str("Calling convention modes are not supported"),
+                _ => return Error::invalid_desc_from_string(format!("Unknown type: {}", char)),
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::Syntax;
+
+    #[test]
+    fn test_jni() {
+        assert_eq!(FuncDesc::parse_with(Syntax::Jni, "(IFJ)V").unwrap(), FuncDesc::new(Box::new([FuncType::S32, FuncType::F32, FuncType::S64]), FuncType::Void));
+        assert_eq!(FuncDesc::parse_with(Syntax::Jni, "(Ljava/lang/String;[[IZ)D").unwrap(), FuncDesc::new(Box::new([FuncType::Pointer, FuncType::Pointer, FuncType::U8]), FuncType::F64));
+        assert!(FuncDesc::parse_with(Syntax::Jni, "(Ljava/lang/String)V").is_err());
+        assert!(FuncDesc::parse_with(Syntax::Jni, "(I").is_err());
+        assert!(FuncDesc::parse_with(Syntax::Jni, "(I)VV").is_err());
+    }
+
+    #[test]
+    fn test_dyncall() {
+        assert_eq!(FuncDesc::parse_with(Syntax::Dyncall, "iif)v").unwrap(), FuncDesc::new(Box::new([FuncType::Int, FuncType::Int, FuncType::F32]), FuncType::Void));
+        assert_eq!(FuncDesc::parse_with(Syntax::Dyncall, ")Z").unwrap(), FuncDesc::new(Box::new([]), FuncType::RefStringPtr));
+        assert!(FuncDesc::parse_with(Syntax::Dyncall, "_si)v").is_err());
+        assert!(FuncDesc::parse_with(Syntax::Dyncall, "ii").is_err());
+        assert!(FuncDesc::parse_with(Syntax::Dyncall, "i)").is_err());
+    }
+}
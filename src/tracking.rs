@@ -0,0 +1,92 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Resource {
+    Library, Adapter, StructType, Error, Struct, String, BoundAdapter
+}
+
+pub(crate) struct Allocation {
+    pub(crate) resource: Resource,
+    pub(crate) site: &'static str,
+}
+
+static ALLOCATIONS: Mutex<Option<HashMap<usize, Allocation>>> = Mutex::new(None);
+
+thread_local! {
+    static SITE: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+pub(crate) struct Site(Option<&'static str>);
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::Library => write!(f, "library"),
+            Resource::Adapter => write!(f, "adapter"),
+            Resource::StructType => write!(f, "struct type"),
+            Resource::Error => write!(f, "error"),
+            Resource::Struct => write!(f, "struct"),
+            Resource::String => write!(f, "string"),
+            Resource::BoundAdapter => write!(f, "bound adapter"),
+        }
+    }
+}
+
+impl Drop for Site {
+    fn drop(&mut self) {
+        SITE.with(|it| it.set(self.0));
+    }
+}
+
+pub(crate) fn lock() -> MutexGuard<'static, Option<HashMap<usize, Allocation>>> {
+    ALLOCATIONS.lock().unwrap_or_else(|it| it.into_inner())
+}
+
+pub(crate) fn enter(site: &'static str) -> Site {
+    Site(SITE.with(|it| it.replace(Some(site))))
+}
+
+pub(crate) fn track(resource: Resource, value: *const c_void) {
+    if value.is_null() { return }
+    let site = if let Some(site) = SITE.with(Cell::get) { site } else { return };
+    if let Some(allocations) = lock().as_mut() {
+        allocations.insert(value as usize, Allocation { resource, site });
+    }
+}
+
+pub(crate) fn untrack(value: *const c_void) {
+    if let Some(allocations) = lock().as_mut() {
+        allocations.remove(&(value as usize));
+    }
+}
+
+pub(crate) fn set_leak_tracking(enabled: bool) {
+    let mut allocations = lock();
+    if enabled != allocations.is_some() {
+        *allocations = if enabled { Some(HashMap::new()) } else { None };
+    }
+}
+
+pub(crate) fn live_count(resource: Resource) -> usize {
+    lock().as_ref().map_or(0, |it| it.values().filter(|it| it.resource == resource).count())
+}
+
+pub(crate) fn report_leaks(resources: &[Resource]) -> usize {
+    let allocations = lock();
+    let allocations = if let Some(allocations) = allocations.as_ref() { allocations } else { return 0 };
+    if allocations.is_empty() { return 0 }
+    eprintln!("sffi: {} leaked allocations", allocations.len());
+    for &resource in resources {
+        let count = allocations.values().filter(|it| it.resource == resource).count();
+        if count > 0 { eprintln!("  {}: {}", resource, count); }
+    }
+    for (value, allocation) in allocations.iter() {
+        eprintln!("  {} {:#x} created by {}", allocation.resource, value, allocation.site);
+    }
+    allocations.len()
+}
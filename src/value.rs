@@ -0,0 +1,460 @@
+use crate::enumeration::{read_integer, write_integer};
+use crate::error::Error;
+use crate::interface::FuncType;
+use crate::structure::{typed_fields, StructType};
+use crate::view::{StructView, StructViewMut};
+use std::ffi::c_void;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::ptr::copy_nonoverlapping;
+
+pub struct StructValue {
+    struct_type: StructType,
+    structure: *mut c_void,
+    strict: bool,
+}
+
+macro_rules! accessors {
+    ($($kind:pat => $get:ident, $set:ident, $type:ty, $name:literal;)+) => {
+        impl StructValue {
+            $(
+                pub fn $get(&self, index: usize) -> Result<$type, Error> {
+                    unsafe { self.struct_type.$get(self.structure, index) }
+                }
+
+                #[allow(clippy::not_unsafe_ptr_arg_deref)]
+                pub fn $set(&mut self, index: usize, value: $type) -> Result<(), Error> {
+                    unsafe { self.struct_type.$set(self.structure, index, value) }
+                }
+            )+
+        }
+    };
+}
+
+typed_fields!(accessors);
+
+macro_rules! values {
+    ($($variant:ident($type:ty, $name:literal),)+) => {
+        #[derive(Debug, Clone)]
+        pub enum Value {
+            $($variant($type),)+
+            ComplexF32(f32, f32),
+            ComplexF64(f64, f64),
+            Enum { name: Option<String>, value: i128 },
+            Flags { names: Vec<String>, residual: i128 },
+            Struct(StructValue),
+        }
+
+        $(
+            impl From<$type> for Value {
+                fn from(value: $type) -> Self {
+                    Value::$variant(value)
+                }
+            }
+
+            impl TryFrom<Value> for $type {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<Self, Error> {
+                    match value {
+                        Value::$variant(value) => Ok(value),
+                        _ => mismatch($name),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+values! {
+    I8(i8, "i8"),
+    I16(i16, "i16"),
+    I32(i32, "i32"),
+    I64(i64, "i64"),
+    I128(i128, "i128"),
+    U8(u8, "u8"),
+    U16(u16, "u16"),
+    U32(u32, "u32"),
+    U64(u64, "u64"),
+    U128(u128, "u128"),
+    ISize(isize, "isize"),
+    USize(usize, "usize"),
+    F32(f32, "f32"),
+    F64(f64, "f64"),
+    Pointer(*mut c_void, "*"),
+}
+
+impl From<(f32, f32)> for Value {
+    fn from((re, im): (f32, f32)) -> Self {
+        Value::ComplexF32(re, im)
+    }
+}
+
+impl TryFrom<Value> for (f32, f32) {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::ComplexF32(re, im) => Ok((re, im)),
+            _ => mismatch("cf32"),
+        }
+    }
+}
+
+impl From<(f64, f64)> for Value {
+    fn from((re, im): (f64, f64)) -> Self {
+        Value::ComplexF64(re, im)
+    }
+}
+
+impl TryFrom<Value> for (f64, f64) {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::ComplexF64(re, im) => Ok((re, im)),
+            _ => mismatch("cf64"),
+        }
+    }
+}
+
+fn mismatch<T>(name: &str) -> Result<T, Error> {
+    Err(Error::InvalidCast { from: None, into: Some(name.into()), message: Some("Value has another type".into()) })
+}
+
+impl From<StructValue> for Value {
+    fn from(value: StructValue) -> Self {
+        Value::Struct(value)
+    }
+}
+
+impl TryFrom<Value> for StructValue {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Struct(value) => Ok(value),
+            _ => mismatch("[]"),
+        }
+    }
+}
+
+macro_rules! fields {
+    ($($kind:pat => $get:ident, $set:ident, $type:ty, $name:literal;)+) => {
+        impl StructValue {
+            pub fn get(&self, index: usize) -> Result<Value, Error> {
+                match self.struct_type.field(index) {
+                    $(Some($kind) => self.$get(index).map(Value::from),)+
+                    _ => self.get_declared(index),
+                }
+            }
+
+            pub fn set(&mut self, index: usize, value: Value) -> Result<(), Error> {
+                match self.struct_type.field(index) {
+                    $(Some($kind) => self.$set(index, value.try_into()?),)+
+                    _ => self.set_declared(index, value),
+                }
+            }
+        }
+    };
+}
+
+typed_fields!(fields);
+
+impl StructValue {
+    fn get_declared(&self, index: usize) -> Result<Value, Error> {
+        match self.struct_type.field(index) {
+            Some(FuncType::ComplexF32) => {
+                let [re, im] = unsafe { self.field_ptr(index).cast::<[f32; 2]>().read_unaligned() };
+                Ok(Value::ComplexF32(re, im))
+            }
+            Some(FuncType::ComplexF64) => {
+                let [re, im] = unsafe { self.field_ptr(index).cast::<[f64; 2]>().read_unaligned() };
+                Ok(Value::ComplexF64(re, im))
+            }
+            Some(field @ FuncType::Enum(enum_type)) => {
+                let value = self.read_integer(index, field);
+                if self.strict && !enum_type.contains(value) { return not_member(field, value) }
+                Ok(Value::Enum { name: enum_type.name_of(value).map(String::from), value })
+            }
+            Some(field @ FuncType::Flags(flags_type)) => {
+                let value = self.read_integer(index, field);
+                if self.strict && value & !flags_type.mask() != 0 { return unknown_bits(field, value) }
+                let (names, residual) = flags_type.decode(value);
+                Ok(Value::Flags { names: names.into_iter().map(String::from).collect(), residual })
+            }
+            _ => {
+                let view = self.view().get_struct_view(index)?;
+                Ok(Value::Struct(unsafe { Self::from_ptr(view.struct_type(), view.as_ptr()) }))
+            }
+        }
+    }
+
+    fn set_declared(&mut self, index: usize, value: Value) -> Result<(), Error> {
+        match self.struct_type.field(index).cloned() {
+            Some(FuncType::ComplexF32) => {
+                let (re, im) = <(f32, f32)>::try_from(value)?;
+                unsafe { self.field_ptr(index).cast::<[f32; 2]>().write_unaligned([re, im]) }
+                Ok(())
+            }
+            Some(FuncType::ComplexF64) => {
+                let (re, im) = <(f64, f64)>::try_from(value)?;
+                unsafe { self.field_ptr(index).cast::<[f64; 2]>().write_unaligned([re, im]) }
+                Ok(())
+            }
+            Some(field @ FuncType::Enum(_)) => {
+                let FuncType::Enum(enum_type) = &field else { unreachable!() };
+                let value = match value {
+                    Value::Enum { name: Some(name), .. } => match enum_type.value_of(&name) {
+                        Some(value) => value,
+                        None => return Err(Error::InvalidCast { from: None, into: Some(field.to_string()), message: Some(format!("Unknown member: {}", name)) }),
+                    },
+                    Value::Enum { name: None, value } => value,
+                    _ => return mismatch(&field.to_string()),
+                };
+                if self.strict && !enum_type.contains(value) { return not_member(&field, value) }
+                self.write_integer(index, &field, value);
+                Ok(())
+            }
+            Some(field @ FuncType::Flags(_)) => {
+                let FuncType::Flags(flags_type) = &field else { unreachable!() };
+                let Value::Flags { names, residual } = value else { return mismatch(&field.to_string()) };
+                let value = flags_type.encode(&names.iter().map(String::as_str).collect::<Vec<_>>())? | residual;
+                if self.strict && value & !flags_type.mask() != 0 { return unknown_bits(&field, value) }
+                self.write_integer(index, &field, value);
+                Ok(())
+            }
+            _ => {
+                let mut view = self.view_mut();
+                let mut view = view.get_struct_view_mut(index)?;
+                let value = StructValue::try_from(value)?;
+                if value.struct_type != *view.struct_type() { return Error::invalid_cast_from_string(&FuncType::Struct(value.struct_type.clone()), &FuncType::Struct(view.struct_type().clone()), format!("Field {} has another type", index)) }
+                unsafe { copy_nonoverlapping(value.structure as *const u8, view.as_mut_ptr() as *mut u8, value.struct_type.size() as usize) }
+                Ok(())
+            }
+        }
+    }
+
+    fn field_ptr(&self, index: usize) -> *mut c_void {
+        unsafe { self.structure.byte_add(self.struct_type.fields()[index].1 as usize) }
+    }
+
+    // Fields of packed layouts may be unaligned, so integers go through an aligned buffer
+    fn read_integer(&self, index: usize, field: &FuncType) -> i128 {
+        let mut buffer = 0u128;
+        unsafe {
+            copy_nonoverlapping(self.field_ptr(index) as *const u8, &mut buffer as *mut u128 as *mut u8, field.size() as usize);
+            read_integer(field, &buffer as *const u128 as *const c_void).unwrap_or_default()
+        }
+    }
+
+    fn write_integer(&mut self, index: usize, field: &FuncType, value: i128) {
+        let mut buffer = 0u128;
+        unsafe {
+            write_integer(field, &mut buffer as *mut u128 as *mut c_void, value);
+            copy_nonoverlapping(&buffer as *const u128 as *const u8, self.field_ptr(index) as *mut u8, field.size() as usize);
+        }
+    }
+}
+
+fn unknown_bits<T>(field: &FuncType, value: i128) -> Result<T, Error> {
+    Err(Error::InvalidCast { from: None, into: Some(field.to_string()), message: Some(format!("Value {:#x} has unknown bits", value)) })
+}
+
+fn not_member<T>(field: &FuncType, value: i128) -> Result<T, Error> {
+    Err(Error::InvalidCast { from: None, into: Some(field.to_string()), message: Some(format!("Value {} is not a member", value)) })
+}
+
+impl StructValue {
+    pub fn new(struct_type: &StructType) -> Self {
+        match Self::try_new(struct_type) {
+            Ok(value) => value,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn try_new(struct_type: &StructType) -> Result<Self, Error> {
+        Ok(Self { struct_type: struct_type.clone(), structure: struct_type.try_calloc()?, strict: true })
+    }
+
+    /// # Safety
+    ///
+    /// `structure` must point to an instance of `struct_type`. The value copies it and keeps no reference.
+    pub unsafe fn from_ptr(struct_type: &StructType, structure: *const c_void) -> Self {
+        let value = Self::new(struct_type);
+        unsafe { copy_nonoverlapping(structure as *const u8, value.structure as *mut u8, struct_type.size() as usize) }
+        value
+    }
+
+    pub fn struct_type(&self) -> &StructType {
+        &self.struct_type
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn as_ptr(&self) -> *const c_void {
+        self.structure
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.structure
+    }
+
+    pub fn view(&self) -> StructView<'_> {
+        unsafe { StructView::new(&self.struct_type, self.structure) }
+    }
+
+    pub fn view_mut(&mut self) -> StructViewMut<'_> {
+        unsafe { StructViewMut::new(&self.struct_type, self.structure) }
+    }
+
+    pub fn by_value(&mut self) -> *mut c_void {
+        self.structure
+    }
+
+    pub fn by_ref(&mut self) -> *mut c_void {
+        &mut self.structure as *mut *mut c_void as *mut c_void
+    }
+}
+
+impl Drop for StructValue {
+    fn drop(&mut self) {
+        StructType::free(self.structure)
+    }
+}
+
+impl Clone for StructValue {
+    fn clone(&self) -> Self {
+        let mut value = unsafe { Self::from_ptr(&self.struct_type, self.structure) };
+        value.strict = self.strict;
+        value
+    }
+}
+
+impl Debug for StructValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StructValue").field(&self.view()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::{FuncDesc, FuncHandle};
+
+    #[repr(C)]
+    struct Pair(i8, f64);
+
+    #[test]
+    fn test_value() {
+        let struct_type = StructType::from_str("[i8,f64,enum Color:u8{Red,Green},flags Mode{Read=1,Write=2},*]").unwrap();
+        let mut value = StructValue::new(&struct_type);
+        assert_eq!(value.as_ptr() as usize % struct_type.align() as usize, 0);
+        value.set_i8(0, -1).unwrap();
+        value.set_f64(1, 1.5).unwrap();
+        value.set_u8(2, 1).unwrap_err();
+        unsafe { value.as_mut_ptr().byte_add(16).cast::<u8>().write(1) }
+        unsafe { value.as_mut_ptr().byte_add(20).cast::<i32>().write(3) }
+
+        let copy = value.clone();
+        value.set_i8(0, 2).unwrap();
+        assert_eq!(copy.get_i8(0).unwrap(), -1);
+        assert_eq!(format!("{:?}", copy), "StructValue([-1, 1.5, Green, Read|Write, 0x0])");
+    }
+
+    #[test]
+    fn test_convert() {
+        let struct_type = StructType::from_str("[u16,[i8,f64],*]").unwrap();
+        let mut value = StructValue::new(&struct_type);
+        value.set(0, Value::U16(7)).unwrap();
+        value.set(0, Value::I16(7)).unwrap_err();
+        assert!(matches!(value.get(0), Ok(Value::U16(7))));
+
+        let mut pair = StructValue::try_from(value.get(1).unwrap()).unwrap();
+        pair.set_f64(1, 2.5).unwrap();
+        value.set(1, Value::from(pair.clone())).unwrap();
+        value.set(2, Value::from(pair)).unwrap_err();
+        value.set(1, Value::from(StructValue::new(&struct_type))).unwrap_err();
+        assert_eq!(format!("{:?}", value), "StructValue([7, [0, 2.5], 0x0])");
+
+        let whole = Value::from(value);
+        assert!(matches!(u16::try_from(whole.clone()), Err(Error::InvalidCast { .. })));
+        assert_eq!(StructValue::try_from(whole).unwrap().get_u16(0).unwrap(), 7);
+        StructValue::try_from(Value::F32(1.0)).unwrap_err();
+    }
+
+    #[test]
+    fn test_enum() {
+        let struct_type = StructType::from_str("[u8,enum Color:u16{Red,Green,Blue=4}]").unwrap();
+        let mut value = StructValue::new(&struct_type);
+        value.set(1, Value::Enum { name: Some("Blue".into()), value: 0 }).unwrap();
+        assert_eq!(value.get_u8(0).unwrap(), 0);
+        assert!(matches!(value.get(1), Ok(Value::Enum { name: Some(name), value: 4 }) if name == "Blue"));
+        value.set(1, Value::Enum { name: None, value: 1 }).unwrap();
+        assert!(matches!(value.get(1), Ok(Value::Enum { name: Some(name), value: 1 }) if name == "Green"));
+
+        value.set(1, Value::Enum { name: Some("Purple".into()), value: 0 }).unwrap_err();
+        value.set(1, Value::Enum { name: None, value: 3 }).unwrap_err();
+        value.set(1, Value::U16(1)).unwrap_err();
+        value.set_strict(false);
+        value.set(1, Value::Enum { name: None, value: 3 }).unwrap();
+        assert!(matches!(value.get(1), Ok(Value::Enum { name: None, value: 3 })));
+        value.set_strict(true);
+        value.get(1).unwrap_err();
+    }
+
+    #[test]
+    fn test_flags() {
+        let struct_type = StructType::from_str("[flags Mode:u8{Read=1,Write=2,Exec=4},i32]").unwrap();
+        let mut value = StructValue::new(&struct_type);
+        value.set(0, Value::Flags { names: vec!["Read".into(), "Exec".into()], residual: 0 }).unwrap();
+        assert_eq!(value.get_i32(1).unwrap(), 0);
+        assert!(matches!(value.get(0), Ok(Value::Flags { names, residual: 0 }) if names == ["Read", "Exec"]));
+
+        value.set(0, Value::Flags { names: vec!["Sticky".into()], residual: 0 }).unwrap_err();
+        value.set(0, Value::Flags { names: vec![], residual: 0x10 }).unwrap_err();
+        value.set(0, Value::U8(1)).unwrap_err();
+        value.set_strict(false);
+        value.set(0, Value::Flags { names: vec!["Write".into()], residual: 0x10 }).unwrap();
+        assert!(matches!(value.get(0), Ok(Value::Flags { names, residual: 0x10 }) if names == ["Write"]));
+        value.set_strict(true);
+        value.get(0).unwrap_err();
+    }
+
+    #[test]
+    fn test_complex() {
+        let struct_type = StructType::from_str("[u8,cf32,cf64]").unwrap();
+        let mut value = StructValue::new(&struct_type);
+        value.set(1, Value::from((1.5f32, -2f32))).unwrap();
+        value.set(2, Value::ComplexF64(3.0, 4.0)).unwrap();
+        value.set(2, Value::from((1f32, 1f32))).unwrap_err();
+        assert_eq!(<(f32, f32)>::try_from(value.get(1).unwrap()).unwrap(), (1.5, -2.0));
+        assert!(matches!(value.get(2), Ok(Value::ComplexF64(3.0, 4.0))));
+        assert_eq!(value.get_u8(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_call() {
+        unsafe extern "C" fn sum(pair: Pair) -> f64 { pair.0 as f64 + pair.1 }
+        unsafe extern "C" fn scale(pair: *mut Pair) { unsafe { (*pair).1 *= 2.0; } }
+        let struct_type = StructType::from_str("[i8,f64]").unwrap();
+        let mut value = StructValue::new(&struct_type);
+        value.set_i8(0, 3).unwrap();
+        value.set_f64(1, 0.25).unwrap();
+
+        let func = FuncHandle::new(scale as *const c_void, FuncDesc::from_str("(*)void").unwrap()).unwrap();
+        unsafe { func.call(std::ptr::null_mut(), &mut [value.by_ref()]); }
+        assert_eq!(value.get_f64(1).unwrap(), 0.5);
+
+        let func = FuncHandle::new(sum as *const c_void, FuncDesc::from_str("([i8,f64])f64").unwrap()).unwrap();
+        let mut result = 0f64;
+        unsafe { func.call(&mut result as *mut f64 as *mut c_void, &mut [value.by_value()]); }
+        assert_eq!(result, 3.5);
+    }
+}
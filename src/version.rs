@@ -0,0 +1,45 @@
+use std::ffi::c_uint;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const ABI_VERSION: c_uint = 2;
+
+pub const FEATURES: &[&str] = &[
+    "syntax-jni",
+    "syntax-dyncall",
+    "pystruct",
+    "codegen",
+    "enum",
+    "flags",
+    "int128",
+    #[cfg(not(windows))]
+    "complex",
+    "packed-layout",
+    "handle-checking",
+    "leak-tracking",
+    "last-error",
+    "introspection",
+    "typed-fields",
+    "struct-value",
+    "struct-view",
+    "struct-array",
+    "allocator",
+    "bound-adapter",
+];
+
+pub fn has_feature(name: &str) -> bool {
+    FEATURES.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features() {
+        assert!(has_feature("syntax-jni"));
+        assert!(!has_feature("variadic"));
+        assert!(!has_feature("closures"));
+        assert!(!has_feature("unions"));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::enumeration::read_integer;
+use crate::error::Error;
+use crate::interface::FuncType;
+use crate::structure::{typed_fields, StructType};
+use std::cmp::{max, min};
+use std::ffi::c_void;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::ptr::copy_nonoverlapping;
+use std::slice;
+
+#[derive(Clone, Copy)]
+pub struct StructView<'a> {
+    struct_type: &'a StructType,
+    structure: *const c_void,
+    _memory: PhantomData<&'a [u8]>,
+}
+
+pub struct StructViewMut<'a> {
+    struct_type: &'a StructType,
+    structure: *mut c_void,
+    _memory: PhantomData<&'a mut [u8]>,
+}
+
+macro_rules! getters {
+    ($($kind:pat => $get:ident, $set:ident, $type:ty, $name:literal;)+) => {
+        impl StructView<'_> {
+            $(
+                pub fn $get(&self, index: usize) -> Result<$type, Error> {
+                    unsafe { self.struct_type.$get(self.structure, index) }
+                }
+            )+
+        }
+
+        impl StructViewMut<'_> {
+            $(
+                pub fn $get(&self, index: usize) -> Result<$type, Error> {
+                    unsafe { self.struct_type.$get(self.structure, index) }
+                }
+
+                #[allow(clippy::not_unsafe_ptr_arg_deref)]
+                pub fn $set(&mut self, index: usize, value: $type) -> Result<(), Error> {
+                    unsafe { self.struct_type.$set(self.structure, index, value) }
+                }
+            )+
+        }
+    };
+}
+
+typed_fields!(getters);
+
+impl<'a> StructView<'a> {
+    /// # Safety
+    ///
+    /// `structure` must point to an instance of `struct_type` that stays readable and unmodified for `'a`.
+    pub unsafe fn new(struct_type: &'a StructType, structure: *const c_void) -> Self {
+        Self { struct_type, structure, _memory: PhantomData }
+    }
+
+    pub fn from_bytes(struct_type: &'a StructType, bytes: &'a [u8]) -> Result<Self, Error> {
+        check_len(struct_type, bytes.len())?;
+        unsafe { Ok(Self::new(struct_type, bytes.as_ptr() as *const c_void)) }
+    }
+
+    pub fn struct_type(&self) -> &'a StructType {
+        self.struct_type
+    }
+
+    pub fn as_ptr(&self) -> *const c_void {
+        self.structure
+    }
+
+    pub fn get_struct_view(&self, index: usize) -> Result<StructView<'a>, Error> {
+        let (struct_type, offset) = self.struct_type.struct_field(index)?;
+        unsafe { Ok(StructView::new(struct_type, self.structure.byte_add(offset))) }
+    }
+
+    /// # Safety
+    ///
+    /// The pointer field must be NULL or point to an instance of `struct_type` that stays valid for `'b`.
+    pub unsafe fn get_ptr_view<'b>(&self, index: usize, struct_type: &'b StructType) -> Result<Option<StructView<'b>>, Error> {
+        let structure = self.get_ptr(index)?;
+        unsafe { Ok(if structure.is_null() { None } else { Some(StructView::new(struct_type, structure)) }) }
+    }
+}
+
+impl<'a> StructViewMut<'a> {
+    /// # Safety
+    ///
+    /// `structure` must point to an instance of `struct_type` that nothing else accesses for `'a`.
+    pub unsafe fn new(struct_type: &'a StructType, structure: *mut c_void) -> Self {
+        Self { struct_type, structure, _memory: PhantomData }
+    }
+
+    pub fn from_bytes(struct_type: &'a StructType, bytes: &'a mut [u8]) -> Result<Self, Error> {
+        check_len(struct_type, bytes.len())?;
+        unsafe { Ok(Self::new(struct_type, bytes.as_mut_ptr() as *mut c_void)) }
+    }
+
+    pub fn struct_type(&self) -> &'a StructType {
+        self.struct_type
+    }
+
+    pub fn as_ptr(&self) -> *const c_void {
+        self.structure
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.structure
+    }
+
+    pub fn as_view(&self) -> StructView<'_> {
+        unsafe { StructView::new(self.struct_type, self.structure) }
+    }
+
+    pub fn get_struct_view_mut(&mut self, index: usize) -> Result<StructViewMut<'_>, Error> {
+        let (struct_type, offset) = self.struct_type.struct_field(index)?;
+        unsafe { Ok(StructViewMut::new(struct_type, self.structure.byte_add(offset))) }
+    }
+
+    /// # Safety
+    ///
+    /// The pointer field must be NULL or point to an instance of `struct_type` that nothing else accesses for `'b`.
+    pub unsafe fn get_ptr_view_mut<'b>(&mut self, index: usize, struct_type: &'b StructType) -> Result<Option<StructViewMut<'b>>, Error> {
+        let structure = self.get_ptr(index)?;
+        unsafe { Ok(if structure.is_null() { None } else { Some(StructViewMut::new(struct_type, structure)) }) }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct StructArrayView<'a> {
+    struct_type: &'a StructType,
+    array: *const c_void,
+    len: usize,
+    _memory: PhantomData<&'a [u8]>,
+}
+
+pub struct StructArrayViewMut<'a> {
+    struct_type: &'a StructType,
+    array: *mut c_void,
+    len: usize,
+    _memory: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> StructArrayView<'a> {
+    /// # Safety
+    ///
+    /// `array` must point to `len` instances of `struct_type` that stay readable and unmodified for `'a`.
+    pub unsafe fn new(struct_type: &'a StructType, array: *const c_void, len: usize) -> Self {
+        Self { struct_type, array, len, _memory: PhantomData }
+    }
+
+    pub fn from_bytes(struct_type: &'a StructType, bytes: &'a [u8]) -> Self {
+        let len = bytes.len() / max(struct_type.stride(), 1) as usize;
+        unsafe { Self::new(struct_type, bytes.as_ptr() as *const c_void, len) }
+    }
+
+    pub fn struct_type(&self) -> &'a StructType {
+        self.struct_type
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const c_void {
+        self.array
+    }
+
+    pub fn get(&self, index: usize) -> Option<StructView<'a>> {
+        if index >= self.len { return None }
+        unsafe { Some(StructView::new(self.struct_type, self.struct_type.element(self.array, index))) }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = StructView<'a>> + 'a {
+        let this = *self;
+        (0..self.len).map(move |index| unsafe { StructView::new(this.struct_type, this.struct_type.element(this.array, index)) })
+    }
+}
+
+impl<'a> StructArrayViewMut<'a> {
+    /// # Safety
+    ///
+    /// `array` must point to `len` instances of `struct_type` that nothing else accesses for `'a`.
+    pub unsafe fn new(struct_type: &'a StructType, array: *mut c_void, len: usize) -> Self {
+        Self { struct_type, array, len, _memory: PhantomData }
+    }
+
+    pub fn from_bytes(struct_type: &'a StructType, bytes: &'a mut [u8]) -> Self {
+        let len = bytes.len() / max(struct_type.stride(), 1) as usize;
+        unsafe { Self::new(struct_type, bytes.as_mut_ptr() as *mut c_void, len) }
+    }
+
+    pub fn struct_type(&self) -> &'a StructType {
+        self.struct_type
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.array
+    }
+
+    pub fn as_view(&self) -> StructArrayView<'_> {
+        unsafe { StructArrayView::new(self.struct_type, self.array, self.len) }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<StructViewMut<'_>> {
+        if index >= self.len { return None }
+        unsafe { Some(StructViewMut::new(self.struct_type, self.struct_type.element(self.array, index) as *mut c_void)) }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = StructViewMut<'_>> {
+        let (struct_type, array) = (self.struct_type, self.array);
+        (0..self.len).map(move |index| unsafe { StructViewMut::new(struct_type, struct_type.element(array, index) as *mut c_void) })
+    }
+}
+
+impl Debug for StructArrayView<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Debug for StructArrayViewMut<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_view().fmt(f)
+    }
+}
+
+fn check_len(struct_type: &StructType, len: usize) -> Result<(), Error> {
+    if len < struct_type.size() as usize { return Error::invalid_args_from_string(format!("Buffer of {} bytes is too small for {}", len, struct_type)) }
+    Ok(())
+}
+
+struct Field<'a>(&'a FuncType, *const c_void);
+
+impl Debug for StructViewMut<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_view().fmt(f)
+    }
+}
+
+impl Debug for StructView<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for (field, offset) in self.struct_type.fields() {
+            list.entry(&Field(field, unsafe { self.structure.byte_add(*offset as usize) }));
+        }
+        list.finish()
+    }
+}
+
+impl Debug for Field<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Field(field, value) = *self;
+        if let FuncType::Struct(struct_type) = field { return unsafe { StructView::new(struct_type, value) }.fmt(f) }
+        // Fields of packed layouts may be unaligned, so they are read from an aligned copy
+        let mut buffer = [0u128; 2];
+        let size = min(field.size() as usize, size_of_val(&buffer));
+        unsafe {
+            copy_nonoverlapping(value as *const u8, buffer.as_mut_ptr() as *mut u8, size);
+            let buffer = buffer.as_ptr() as *const c_void;
+            match field {
+                FuncType::Enum(enumeration) => {
+                    let value = read_integer(field, buffer).unwrap_or_default();
+                    match enumeration.name_of(value) {
+                        Some(name) => write!(f, "{}", name),
+                        None => write!(f, "{}", value),
+                    }
+                }
+                FuncType::Flags(flags) => write!(f, "{}", flags.decode_str(read_integer(field, buffer).unwrap_or_default())),
+                FuncType::S128 => write!(f, "{}", buffer.cast::<i128>().read()),
+                FuncType::U128 => write!(f, "{}", buffer.cast::<u128>().read()),
+                FuncType::F32 | FuncType::Float => write!(f, "{:?}", buffer.cast::<f32>().read()),
+                FuncType::F64 | FuncType::Double => write!(f, "{:?}", buffer.cast::<f64>().read()),
+                FuncType::ComplexF32 => write!(f, "{:?}", buffer.cast::<[f32; 2]>().read()),
+                FuncType::ComplexF64 => write!(f, "{:?}", buffer.cast::<[f64; 2]>().read()),
+                _ if field.is_integer() => write!(f, "{}", read_integer(field, buffer).unwrap_or_default()),
+                _ if field.is_ptr() => write!(f, "{:?}", buffer.cast::<*const c_void>().read()),
+                _ => write!(f, "{:02x?}", slice::from_raw_parts(buffer as *const u8, size)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes() {
+        let struct_type = StructType::from_str("[u8,[i16,i32]]").unwrap();
+        let mut bytes = [0u8; 12];
+        assert!(matches!(StructView::from_bytes(&struct_type, &bytes[..8]).unwrap_err(), Error::InvalidArguments { .. }));
+        let mut view = StructViewMut::from_bytes(&struct_type, &mut bytes).unwrap();
+        view.set_u8(0, 7).unwrap();
+        view.get_struct_view_mut(1).unwrap().set_i32(1, -2).unwrap();
+        assert!(view.set_i16(1, 0).is_err());
+        assert_eq!(format!("{:?}", view), "[7, [0, -2]]");
+        assert_eq!(bytes[8..], (-2i32).to_ne_bytes());
+        let view = StructView::from_bytes(&struct_type, &bytes).unwrap();
+        assert_eq!(view.get_struct_view(1).unwrap().get_i32(1).unwrap(), -2);
+    }
+
+    #[test]
+    fn test_pointer() {
+        let node = StructType::from_str("[i32,*]").unwrap();
+        let mut last = [0u8; 16];
+        let mut first = [0u8; 16];
+        StructViewMut::from_bytes(&node, &mut last).unwrap().set_i32(0, 2).unwrap();
+        let mut view = StructViewMut::from_bytes(&node, &mut first).unwrap();
+        view.set_i32(0, 1).unwrap();
+        view.set_ptr(1, last.as_mut_ptr() as *mut c_void).unwrap();
+        unsafe {
+            let next = view.get_ptr_view_mut(1, &node).unwrap().unwrap();
+            assert_eq!(next.get_i32(0).unwrap(), 2);
+            assert!(next.as_view().get_ptr_view(1, &node).unwrap().is_none());
+            assert!(view.get_ptr_view_mut(0, &node).is_err());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_foreign() {
+        let mut lib = crate::library::LibHandle::open("").unwrap();
+        let gmtime = lib.func("gmtime", "(*)*").unwrap();
+        let tm = StructType::from_str("[int,int,int,int,int,int,int,int,int]").unwrap();
+        let time = 86400 * 365 + 3600 * 5 + 60 * 4 + 3i64;
+        let mut result = std::ptr::null::<c_void>();
+        unsafe {
+            gmtime.call(&mut result as *mut *const c_void as *mut c_void, &mut [&mut &time as *mut &i64 as *mut c_void]);
+            let view = StructView::new(&tm, result);
+            assert_eq!((view.get_i32(0).unwrap(), view.get_i32(1).unwrap(), view.get_i32(2).unwrap()), (3, 4, 5));
+            assert_eq!(view.get_i32(5).unwrap(), 71);
+        }
+    }
+
+    #[test]
+    fn test_array() {
+        unsafe extern "C" fn sum(array: *const [i64; 2], len: usize) -> i64 {
+            unsafe { (0..len).map(|index| (*array.add(index))[0] * (*array.add(index))[1]).sum() }
+        }
+        let struct_type = StructType::from_str("[i64,i8]").unwrap();
+        let array = struct_type.alloc_array(3);
+        let mut view = unsafe { StructArrayViewMut::new(&struct_type, array, 3) };
+        for (index, mut element) in view.iter_mut().enumerate() {
+            element.set_i64(0, index as i64 + 1).unwrap();
+            element.set_i8(1, 2).unwrap();
+        }
+        assert!(view.get_mut(3).is_none());
+        assert_eq!(format!("{:?}", view), "[[1, 2], [2, 2], [3, 2]]");
+        assert_eq!(view.as_view().get(1).unwrap().get_i64(0).unwrap(), 2);
+
+        let func = crate::interface::FuncHandle::new(sum as *const c_void, crate::interface::FuncDesc::from_str("(*,usize)i64").unwrap()).unwrap();
+        let mut result = 0i64;
+        let mut len = view.len();
+        let mut array_ = view.as_mut_ptr();
+        unsafe { func.call(&mut result as *mut i64 as *mut c_void, &mut [&mut array_ as *mut *mut c_void as *mut c_void, &mut len as *mut usize as *mut c_void]); }
+        assert_eq!(result, 12);
+        StructType::free(array);
+    }
+}
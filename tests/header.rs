@@ -0,0 +1,256 @@
+#![cfg(target_os = "linux")]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PROGRAM: &str = r#"
+#include <sffi.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static void *const EXPORTS[] = { EXPORTS_LIST };
+
+STATIC_ASSERTS
+
+/* Fails to compile if a prototype of the header differs from the Rust definition */
+static void check_prototypes(void) {
+PROTOTYPES
+}
+
+static void *counting_alloc(size_t size, size_t align, void *data) { (void) align; ++*(int *) data; return malloc(size); }
+static void counting_free(void *ptr, void *data) { --*(int *) data; free(ptr); }
+
+#define CHECK(condition) if (!(condition)) { fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #condition); return 1; }
+
+int main(void) {
+    check_prototypes();
+    for (size_t i = 0; i < sizeof(EXPORTS) / sizeof(*EXPORTS); i++) CHECK(EXPORTS[i] != NULL);
+
+    CHECK(sffi_abi_version() == 2);
+    CHECK(strlen(sffi_version()) > 0);
+    CHECK(sffi_has_feature("last-error"));
+
+    sffi_lib *lib = NULL;
+    CHECK(sffi_lib_open(&lib, "") == NULL);
+    const sffi_func *func = NULL;
+    CHECK(sffi_lib_func(&func, lib, "abs", "(int)int") == NULL);
+    int argument = -42, result = 0;
+    void *arguments[] = { &argument };
+    CHECK(sffi_func_call_checked(func, &result, arguments) == NULL);
+    CHECK(result == 42);
+
+    sffi_adapter *adapter = NULL;
+    CHECK(sffi_adapter_parse(&adapter, "(i8)i64") == NULL);
+    sffi_bound_adapter *bound = NULL;
+    CHECK(sffi_adapter_bind(&bound, adapter, func) == NULL);
+    sffi_adapter_free(adapter);
+    int8_t small = -7;
+    int64_t wide_result = 0;
+    void *small_arguments[] = { &small };
+    CHECK(sffi_bound_adapter_call(bound, &wide_result, 1, small_arguments) == NULL);
+    CHECK(wide_result == 7);
+    CHECK(sffi_bound_adapter_call_status(bound, &wide_result, 0, small_arguments) == SFFI_INVALID_ARGUMENTS_ERR);
+    sffi_bound_adapter_free(bound);
+
+    sffi_struct_type *struct_type = NULL;
+    sffi_error *error = sffi_struct_type_alloc(&struct_type, "[i32,q32]");
+    CHECK(sffi_error_code(error) == SFFI_INVALID_DESCRIPTOR_ERR);
+    CHECK(strcmp(sffi_error_msg(error), "Unknown type: q32") == 0);
+    CHECK(strcmp(sffi_error_report(error), "Invalid descriptor '[i32,q32]': Unknown type: q32") == 0);
+    sffi_error_free(error);
+
+    CHECK(sffi_struct_type_alloc_status(&struct_type, "[i32,i64]") == SFFI_NO_ERR);
+    CHECK(sffi_struct_type_size(struct_type) == 16);
+    void *structure = sffi_struct_calloc(struct_type);
+    int value = 7;
+    sffi_struct_set_raw(struct_type, structure, 0, &value);
+    value = 0;
+    CHECK(sffi_struct_get_raw_checked(struct_type, structure, 0, &value) == NULL);
+    CHECK(value == 7);
+    CHECK(sffi_struct_get_raw_status(struct_type, structure, 2, &value) == SFFI_INVALID_ARGUMENTS_ERR);
+    CHECK(sffi_last_error_msg() != NULL);
+    int64_t wide = 0;
+    CHECK(sffi_struct_set_i64(struct_type, structure, 1, -5) == NULL);
+    CHECK(sffi_struct_get_i64(&wide, struct_type, structure, 1) == NULL);
+    CHECK(wide == -5);
+    CHECK(sffi_struct_get_i32_status(&value, struct_type, structure, 1) == SFFI_INVALID_CAST_ERR);
+    sffi_struct_free(structure);
+
+    int live = 0;
+    CHECK(sffi_set_allocator(counting_alloc, counting_free, &live) == NULL);
+    structure = sffi_struct_malloc(struct_type);
+    CHECK(live == 1);
+    sffi_struct_free(structure);
+    CHECK(live == 0);
+    CHECK(sffi_set_allocator_status(counting_alloc, NULL, NULL) == SFFI_INVALID_ARGUMENTS_ERR);
+    CHECK(sffi_set_allocator(NULL, NULL, NULL) == NULL);
+    sffi_struct_type_free(struct_type);
+
+    sffi_lib_close(lib);
+    return 0;
+}
+"#;
+
+fn deps_dir() -> PathBuf {
+    std::env::current_exe().unwrap().parent().unwrap().to_path_buf()
+}
+
+fn declarations(header: &str) -> BTreeSet<String> {
+    header.lines()
+        .filter(|line| line.ends_with(");") && !line.starts_with(' ') && !line.starts_with("typedef"))
+        .map(|line| {
+            let name = &line[..line.find('(').unwrap()];
+            name.rsplit([' ', '*']).next().unwrap().to_string()
+        })
+        .collect()
+}
+
+fn c_type(rust: &str) -> String {
+    let pointer = |inner: String| if inner.ends_with('*') { format!("{}*", inner) } else { format!("{} *", inner) };
+    if let Some(inner) = rust.strip_prefix("*mut ") { return pointer(c_type(inner)) }
+    if let Some(inner) = rust.strip_prefix("*const ") { return pointer(format!("const {}", c_type(inner))) }
+    match rust {
+        "" => "void",
+        "bool" => "bool",
+        "c_char" => "char",
+        "c_uint" => "unsigned int",
+        "c_void" => "void",
+        // Opaque to the host, `avalue` of the raw calls is declared as `void *`
+        "ffi_raw" => "void",
+        "i8" => "int8_t", "i16" => "int16_t", "i32" => "int32_t", "i64" => "int64_t",
+        "u8" => "uint8_t", "u16" => "uint16_t", "u32" => "uint32_t", "u64" => "uint64_t",
+        "isize" => "ptrdiff_t",
+        "usize" => "size_t",
+        "f32" => "float",
+        "f64" => "double",
+        "Adapter" => "sffi_adapter",
+        "BoundAdapter<'static>" => "sffi_bound_adapter",
+        "CError" => "sffi_error",
+        "FuncHandle" => "sffi_func",
+        "LibHandle" => "sffi_lib",
+        "StructType" => "sffi_struct_type",
+        "Option<AllocFn>" => "sffi_alloc_fn",
+        "Option<FreeFn>" => "sffi_free_fn",
+        _ => panic!("no C type for {}", rust),
+    }.into()
+}
+
+fn c_pointer(arguments: &str, result: &str) -> String {
+    let arguments = arguments.split(", ").filter(|it| !it.is_empty()).map(|it| c_type(it.split_once(": ").unwrap().1)).collect::<Vec<_>>();
+    let arguments = if arguments.is_empty() { "void".into() } else { arguments.join(", ") };
+    format!("{} (*pointer)({})", c_type(result), arguments)
+}
+
+fn prototypes(api: &Path) -> BTreeMap<String, String> {
+    let mut prototypes = BTreeMap::new();
+    for entry in fs::read_dir(api).unwrap() {
+        let source = fs::read_to_string(entry.unwrap().path()).unwrap();
+        for line in source.lines().map(str::trim) {
+            if let Some((_, rest)) = line.split_once("extern \"C\" fn sffi_") {
+                let (name, rest) = rest.split_once('(').unwrap();
+                let (arguments, rest) = rest.split_once(')').unwrap();
+                let result = rest.trim_start().strip_prefix("-> ").map_or("", |it| it.trim_end_matches(" {").trim());
+                prototypes.insert(format!("sffi_{}", name), c_pointer(arguments, result));
+            } else if let Some((name, rest)) = line.split_once(" => ") && name.starts_with("sffi_") {
+                if let Some((get, set)) = name.split_once(", ") {
+                    // typed!: `get, set => rust_get, rust_set: type;`
+                    let r#type = rest.split_once(": ").unwrap().1.trim_end_matches(';');
+                    let getter = format!("r_value: *mut {}, struct_type: *const StructType, structure: *const c_void, index: c_uint", r#type);
+                    let setter = format!("struct_type: *const StructType, structure: *mut c_void, index: c_uint, value: {}", r#type);
+                    prototypes.insert(get.into(), c_pointer(&getter, "*mut CError"));
+                    prototypes.insert(set.into(), c_pointer(&setter, "*mut CError"));
+                } else {
+                    // status!: `name => func(arguments);`
+                    let arguments = rest.split_once('(').unwrap().1.trim_end_matches(");");
+                    prototypes.insert(name.into(), c_pointer(arguments, "c_uint"));
+                }
+            }
+        }
+    }
+    prototypes
+}
+
+fn hooks(api: &Path) -> BTreeMap<String, String> {
+    let source = fs::read_to_string(api.join("allocator.rs")).unwrap();
+    source.lines()
+        .filter_map(|line| line.strip_prefix("pub type "))
+        .map(|line| {
+            let (name, rest) = line.split_once(" = unsafe extern \"C\" fn(").unwrap();
+            let (arguments, rest) = rest.split_once(')').unwrap();
+            let result = rest.trim_start().strip_prefix("-> ").map_or("", |it| it.trim_end_matches(';'));
+            (c_type(&format!("Option<{}>", name)), c_pointer(arguments, result))
+        })
+        .collect()
+}
+
+fn constants(api: &Path) -> BTreeMap<String, String> {
+    let mut constants = BTreeMap::new();
+    for entry in fs::read_dir(api).unwrap() {
+        let source = fs::read_to_string(entry.unwrap().path()).unwrap();
+        for line in source.lines() {
+            let Some(rest) = line.strip_prefix("pub const SFFI_") else { continue };
+            let (name, value) = rest.split_once(':').unwrap();
+            let value = value.split_once('=').unwrap().1.trim().trim_end_matches(';');
+            constants.insert(format!("SFFI_{}", name.trim()), value.into());
+        }
+    }
+    constants
+}
+
+fn exports(library: &Path) -> BTreeSet<String> {
+    let output = Command::new("nm").args(["-D", "--defined-only"]).arg(library).output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap().lines()
+        .filter_map(|line| line.split_whitespace().nth(2))
+        .filter(|name| name.starts_with("sffi_"))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn test_header() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let include = root.join("include");
+    let header = fs::read_to_string(include.join("sffi.h")).unwrap();
+    let target = deps_dir();
+
+    let declared = declarations(&header);
+    let exported = exports(&target.join("libsffi.so"));
+    assert_eq!(declared.difference(&exported).collect::<Vec<_>>(), Vec::<&String>::new(), "declared but not exported");
+    assert_eq!(exported.difference(&declared).collect::<Vec<_>>(), Vec::<&String>::new(), "exported but not declared");
+
+    let api = root.join("src").join("api");
+    let prototypes = prototypes(&api);
+    assert_eq!(prototypes.keys().cloned().collect::<BTreeSet<_>>(), declared, "declared in the header and the Rust sources");
+    let constants = constants(&api);
+    let defined = header.lines().filter_map(|line| line.strip_prefix("#define SFFI_")).filter_map(|it| it.split_whitespace().next())
+        .filter(|it| *it != "H").map(|it| format!("SFFI_{}", it)).collect::<BTreeSet<_>>();
+    assert_eq!(constants.keys().cloned().collect::<BTreeSet<_>>(), defined, "defined in the header and the Rust sources");
+
+    let directory = target.parent().unwrap().join("header");
+    fs::create_dir_all(&directory).unwrap();
+    let list = declared.iter().map(|name| format!("(void *) &{}", name)).collect::<Vec<_>>().join(", ");
+    let source = directory.join("main.c");
+    let asserts = constants.iter().map(|(name, value)| format!("_Static_assert({} == {}u, \"{}\");", name, value, name)).collect::<Vec<_>>().join("\n");
+    let pointers = prototypes.iter().map(|(name, pointer)| format!("    {{ {} = {}; (void) pointer; }}", pointer, name))
+        .chain(hooks(&api).iter().map(|(name, pointer)| format!("    {{ {} hook = NULL; {} = hook; (void) pointer; }}", name, pointer)))
+        .collect::<Vec<_>>().join("\n");
+    let program = PROGRAM.replace("EXPORTS_LIST", &list).replace("STATIC_ASSERTS", &asserts).replace("PROTOTYPES", &pointers);
+    fs::write(&source, program).unwrap();
+
+    let program = directory.join("main");
+    let status = Command::new("cc")
+        .args(["-std=c11", "-Wall", "-Werror"])
+        .arg("-I").arg(&include)
+        .arg(&source)
+        .arg("-o").arg(&program)
+        .arg("-L").arg(&target)
+        .arg("-lsffi")
+        .status().unwrap();
+    assert!(status.success());
+    // Cargo's library path also lists the directory of the last `cargo build`, which may hold an older copy.
+    assert!(Command::new(&program).env("LD_LIBRARY_PATH", &target).status().unwrap().success());
+}